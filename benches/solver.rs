@@ -0,0 +1,115 @@
+//! Benchmarks of the figure and board operations the search leans on,
+//! and of whole searches. Run with `cargo bench`, optionally followed by
+//! `-- <filter>` to only run the benchmarks whose names contain it.
+//!
+//! Each benchmark is run in batches long enough to time reliably, and
+//! the fastest and median time per run of the batches are reported.
+
+use std::env;
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use inbento_solver::*;
+
+const SAMPLES: usize = 10;
+const SAMPLE_TIME: Duration = Duration::from_millis(20);
+
+struct Bencher {
+    filter: Option<String>,
+}
+
+impl Bencher {
+    fn bench<T>(&self, name: &str, mut f: impl FnMut() -> T) {
+        if self.filter.as_ref().is_some_and(|filter| !name.contains(filter.as_str())) {
+            return;
+        }
+        // find how many runs make a sample long enough to time.
+        let mut runs = 1;
+        loop {
+            let started = Instant::now();
+            for _ in 0..runs {
+                black_box(f());
+            }
+            if started.elapsed() >= SAMPLE_TIME || runs >= 1 << 30 {
+                break;
+            }
+            runs *= 2;
+        }
+        let mut samples: Vec<Duration> = (0..SAMPLES)
+            .map(|_| {
+                let started = Instant::now();
+                for _ in 0..runs {
+                    black_box(f());
+                }
+                started.elapsed() / runs
+            })
+            .collect();
+        samples.sort();
+        println!("{name:<40} {:>12.2?} {:>12.2?}", samples[0], samples[SAMPLES / 2]);
+    }
+}
+
+fn main() {
+    // cargo passes `--bench`, and any other flags, along to us.
+    let filter = env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    let bencher = Bencher { filter };
+    println!("{:<40} {:>12} {:>12}", "benchmark", "fastest", "median");
+    figures(&bencher);
+    boards(&bencher);
+    searches(&bencher);
+}
+
+fn figures(bencher: &Bencher) {
+    let piece = Piece::from_str("(22)(.2)").unwrap();
+    bencher.bench("all_transformations/piece", || black_box(&piece).all_transformations());
+    let push = Push::from_str("(v<)").unwrap();
+    bencher.bench("all_transformations/push", || black_box(&push).all_transformations());
+    let shape = Shape::from_str("(##)(#.)").unwrap();
+    bencher.bench("all_transformations/shape", || black_box(&shape).all_transformations());
+    let swap = Swap::from_str("(#.#)").unwrap();
+    bencher.bench("all_transformations/swap", || black_box(&swap).all_transformations());
+    let copy = CopyPaste::from_str("(CC.)(.VV)").unwrap();
+    bencher.bench("all_transformations/copy", || black_box(&copy).all_transformations());
+    let cycle = Cycle::from_str("(12)(43)").unwrap();
+    bencher.bench("all_transformations/cycle", || black_box(&cycle).all_transformations());
+    let rotate = Rotate::from_str("(RRR)(R.R)(RRR)").unwrap();
+    bencher.bench("all_transformations/rotate", || black_box(&rotate).all_transformations());
+}
+
+/// Each `apply_*` is timed over every placement of its tool, to cover
+/// moves in each direction and at each edge.
+fn boards(bencher: &Bencher) {
+    let board = Board::from_str("[131][1.1][113]").unwrap();
+    let pushes = Push::from_str("(v<)").unwrap().all_transformations();
+    bencher.bench("apply_push", || each(&pushes, |push| board.apply_push(push)));
+    bencher.bench("apply_push_chain", || each(&pushes, |push| board.apply_push_chain(push)));
+    let slides = Slide::from_str("(>.)").unwrap().all_transformations();
+    bencher.bench("apply_slide", || each(&slides, |slide| board.apply_slide(slide)));
+    let shapes = Shape::from_str("(##)").unwrap().all_transformations();
+    bencher.bench("apply_lift", || each(&shapes, |shape| board.apply_lift(shape)));
+    let pieces = Piece::from_str("(22)(.2)").unwrap().all_transformations();
+    bencher.bench("apply_piece", || each(&pieces, |piece| board.apply_piece(piece)));
+    let copies = CopyPaste::from_str("(CC.)(.VV)").unwrap().all_transformations();
+    bencher.bench("apply_copy", || each(&copies, |copy| board.apply_copy(copy)));
+    let swaps = Swap::from_str("(#.#)").unwrap().all_transformations();
+    bencher.bench("apply_swap", || each(&swaps, |swap| board.apply_swap(swap)));
+    let cycles = Cycle::from_str("(12)(43)").unwrap().all_transformations();
+    bencher.bench("apply_cycle", || each(&cycles, |cycle| board.apply_cycle(cycle)));
+    let rotates = Rotate::from_str("(RRR)(R.R)(RRR)").unwrap().all_transformations();
+    bencher.bench("apply_rotate", || each(&rotates, |rotate| board.apply_rotate(rotate)));
+}
+
+/// Run `f` on each of the items, keeping the results from being
+/// optimized away.
+fn each<T, U>(items: &[T], f: impl Fn(&T) -> U) {
+    for item in items {
+        black_box(f(item));
+    }
+}
+
+fn searches(bencher: &Bencher) {
+    let rules = Rules::default();
+    for Level { id, puzzle } in catalog() {
+        bencher.bench(&format!("solve/{id}"), || solve(&puzzle.board, &puzzle.goal, &puzzle.tools, &rules).unwrap());
+    }
+}
@@ -0,0 +1,25 @@
+//! Every parser should reject bad input with an error, never a panic,
+//! and every tool it accepts should be safe to place and apply.
+
+#![no_main]
+
+use std::str::FromStr;
+use inbento_solver::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(string) = std::str::from_utf8(data) else { return };
+    let _ = Board::from_str(string);
+    let _ = Shape::from_str(string);
+    let _ = Puzzle::from_str(string);
+    let board = Board::from_str("[1.2][3.4][.5.]").unwrap();
+    let rules = Rules::default();
+    for kind in Registry::default().names() {
+        let Ok(tool) = Tool::parse(kind, string) else { continue };
+        let _ = tool.reach(&rules);
+        let _ = tool.food_effect(&rules);
+        for placed in tool.transformations() {
+            let _ = placed.apply(&board, &rules);
+        }
+    }
+});
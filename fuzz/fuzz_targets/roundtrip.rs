@@ -0,0 +1,30 @@
+//! Whatever parses should be written back out (with `Debug`, `Display`,
+//! or as a puzzle file) as something which parses to the same value.
+
+#![no_main]
+
+use std::str::FromStr;
+use inbento_solver::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(string) = std::str::from_utf8(data) else { return };
+    let _ = string.parse::<Tool>();
+    if let Ok(board) = Board::from_str(string) {
+        assert_eq!(Board::from_str(&format!("{board:?}")).as_ref(), Ok(&board));
+        assert_eq!(board.to_string().parse(), Ok(board));
+    }
+    if let Ok(shape) = Shape::from_str(string) {
+        assert_eq!(Shape::from_str(&format!("{shape:?}")).as_ref(), Ok(&shape));
+        assert_eq!(shape.to_string().parse(), Ok(shape));
+    }
+    for kind in Registry::default().names() {
+        let Ok(tool) = Tool::parse(kind, string) else { continue };
+        assert_eq!(tool.name(), kind);
+        assert_eq!(Tool::parse(kind, &tool.kind().to_string()).as_ref(), Ok(&tool));
+        assert_eq!(tool.to_string().parse(), Ok(tool));
+    }
+    if let Ok(puzzle) = Puzzle::from_str(string) {
+        assert_eq!(Puzzle::from_str(&puzzle.to_string()), Ok(puzzle));
+    }
+});
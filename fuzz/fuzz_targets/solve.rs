@@ -0,0 +1,22 @@
+//! Solving any puzzle should either find a solution which really does
+//! reach the goal, or say why not, never panic.
+
+#![no_main]
+
+use std::str::FromStr;
+use inbento_solver::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(string) = std::str::from_utf8(data) else { return };
+    let Ok(puzzle) = Puzzle::from_str(string) else { return };
+    let options = SolveOptions { max_states: Some(10_000), ..Default::default() };
+    let _ = check(&puzzle.board, &puzzle.goal, &puzzle.tools, &options.rules);
+    let Ok(solution) = solve_with(&puzzle.board, &puzzle.goal, &puzzle.tools, &options) else { return };
+    let mut board = puzzle.board.clone();
+    for (step, tool) in solution {
+        assert_eq!(step, board);
+        board = tool.apply(&board, &options.rules).0;
+    }
+    assert_eq!(board, puzzle.goal);
+});
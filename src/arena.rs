@@ -0,0 +1,302 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem::size_of;
+use crate::solver::SolveError;
+use crate::tools::{Board, Rules, Symmetry, Tool, AREA};
+
+/// A state of the search packed into a single number: four bits for
+/// each cell of the board, and the id of the set of tools left above
+/// them.
+pub(crate) type Packed = u64;
+
+const CELL_BITS: usize = 4;
+const BOARD_BITS: usize = CELL_BITS * AREA;
+const EMPTY_CELL: u64 = 0xf;
+
+pub(crate) fn pack_board(board: &Board) -> u64 {
+    // `solve_with` turns away boards with cells which don't fit, but a
+    // tool could still make one.
+    assert!(board.cells().iter().flatten().all(|&cell| u64::from(cell) < EMPTY_CELL), "cells must fit in {CELL_BITS} bits");
+    board.cells().iter().enumerate()
+        .map(|(idx, cell)| cell.map_or(EMPTY_CELL, u64::from) << (idx * CELL_BITS))
+        .fold(0, |bits, cell| bits | cell)
+}
+
+/// Numbers each distinct value the first time it's seen.
+struct Interner<T> {
+    ids: HashMap<T, u32>,
+    values: Vec<T>,
+}
+
+impl<T> Default for Interner<T> {
+    fn default() -> Self {
+        Interner { ids: HashMap::new(), values: Vec::new() }
+    }
+}
+
+impl<T: Clone + Eq + Hash> Interner<T> {
+    fn intern(&mut self, value: T) -> u32 {
+        match self.ids.entry(value) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                self.values.push(entry.key().clone());
+                *entry.insert(self.values.len() as u32 - 1)
+            }
+        }
+    }
+}
+
+/// Packs states into `Packed` keys and back, numbering the sets of
+/// tools and the moves it sees along the way.
+pub(crate) struct Packer {
+    start: Board,
+    toolsets: Interner<Vec<Tool>>,
+    moves: Interner<Tool>,
+}
+
+impl Packer {
+    /// Boards are unpacked onto a clone of `start`.
+    pub(crate) fn new(start: &Board) -> Self {
+        Packer { start: start.clone(), toolsets: Interner::default(), moves: Interner::default() }
+    }
+
+    /// Returns the id of the set of tools, which should be in
+    /// `canonical` order.
+    pub(crate) fn toolset(&mut self, tools: Vec<Tool>) -> u32 {
+        let id = self.toolsets.intern(tools);
+        assert!((id as u64) < 1 << (64 - BOARD_BITS), "too many sets of tools to pack");
+        id
+    }
+
+    pub(crate) fn pack(&self, board: &Board, toolset: u32) -> Packed {
+        pack_board(board) | (toolset as u64) << BOARD_BITS
+    }
+
+    pub(crate) fn board_bits(key: Packed) -> u64 {
+        key & ((1 << BOARD_BITS) - 1)
+    }
+
+    /// Returns the board and tools of the state.
+    pub(crate) fn unpack(&self, key: Packed) -> (Board, &[Tool]) {
+        let mut board = self.start.clone();
+        for (idx, cell) in board.cells_mut().iter_mut().enumerate() {
+            let bits = key >> (idx * CELL_BITS) & EMPTY_CELL;
+            *cell = (bits != EMPTY_CELL).then_some(bits as u8);
+        }
+        (board, self.tools(key))
+    }
+
+    pub(crate) fn tools(&self, key: Packed) -> &[Tool] {
+        &self.toolsets.values[(key >> BOARD_BITS) as usize]
+    }
+
+    /// Returns the id of the move.
+    pub(crate) fn action(&mut self, action: &Tool) -> u32 {
+        self.moves.intern(action.clone())
+    }
+
+    /// Returns the moves which lead from the start, given as each move's
+    /// id and the symmetry which turned the board it left, along with the
+    /// board each is made on. Boards stored turned by a symmetry are
+    /// turned back, along with the moves made on them.
+    pub(crate) fn replay(&self, steps: &[(u32, Symmetry)], rules: &Rules) -> Result<Vec<(Board, Tool)>, SolveError> {
+        let mut board = self.start.clone();
+        let mut turned = Symmetry::IDENTITY;
+        let mut path = Vec::new();
+        for &(action, symmetry) in steps {
+            let action = &self.moves.values[action as usize];
+            let undo = turned.inverse();
+            let action = if undo == Symmetry::IDENTITY {
+                action.clone()
+            } else {
+                action.map_symmetry(undo).ok_or_else(|| SolveError::Unmapped(action.clone()))?
+            };
+            let next = action.apply(&board, rules).0;
+            path.push((board, action));
+            board = next;
+            turned = turned.then(symmetry);
+        }
+        Ok(path)
+    }
+
+    /// A rough estimate, in bytes, of the memory taken by the sets of tools.
+    pub(crate) fn memory_estimate(&self) -> usize {
+        self.toolsets.values.iter().map(|tools| tools.len() * size_of::<Tool>()).sum()
+    }
+}
+
+/// How the search first reached a state: the state it came from, the
+/// move made, and the symmetry which turned the resulting board into
+/// the one stored.
+struct Node {
+    parent: u32,
+    action: u32,
+    symmetry: Symmetry,
+}
+
+/// Marks a slot of the index with no state in it.
+const NO_STATE: u32 = u32::MAX;
+
+/// Every state the search has found, numbered in the order found.
+///
+/// Rather than keeping each board and set of tools, states are packed
+/// into a `Packed`, with the sets of tools (of which there are few) and
+/// moves numbered separately. Paths are rebuilt by replaying the moves
+/// from the start, so each state costs its packed key, a `Node`, and a
+/// slot or two of the index.
+pub(crate) struct Arena {
+    pub(crate) packer: Packer,
+    keys: Vec<Packed>,
+    nodes: Vec<Node>,
+    /// An open-addressed hash table of ids, found by the key each has in
+    /// `keys`, so the keys aren't kept twice. Its length is a power of two.
+    index: Vec<u32>,
+}
+
+impl Arena {
+    /// Start the arena with the first state, which gets id 0. The tools
+    /// should be in `canonical` order.
+    pub(crate) fn new(start: &Board, tools: Vec<Tool>) -> Self {
+        let mut packer = Packer::new(start);
+        let toolset = packer.toolset(tools);
+        let key = packer.pack(start, toolset);
+        let mut arena = Arena {
+            packer,
+            keys: vec![key],
+            nodes: vec![Node { parent: 0, action: 0, symmetry: Symmetry::IDENTITY }],
+            index: vec![NO_STATE; 16],
+        };
+        let slot = arena.slot(key);
+        arena.index[slot] = 0;
+        arena
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub(crate) fn key(&self, id: u32) -> Packed {
+        self.keys[id as usize]
+    }
+
+    /// Add the state reached from `parent`, unless it's already been
+    /// found. Returns its id, and whether it's new.
+    pub(crate) fn insert(&mut self, key: Packed, parent: u32, action: &Tool, symmetry: Symmetry) -> (u32, bool) {
+        let slot = self.slot(key);
+        if self.index[slot] != NO_STATE {
+            return (self.index[slot], false);
+        }
+        let id = self.keys.len() as u32;
+        self.index[slot] = id;
+        self.keys.push(key);
+        let action = self.packer.action(action);
+        self.nodes.push(Node { parent, action, symmetry });
+        // keep the index at most 7/8 full, so probes stay short.
+        if self.keys.len() * 8 > self.index.len() * 7 {
+            self.index = vec![NO_STATE; self.index.len() * 2];
+            for id in 0..self.keys.len() {
+                let slot = self.slot(self.keys[id]);
+                self.index[slot] = id as u32;
+            }
+        }
+        (id, true)
+    }
+
+    /// Returns the slot of the index which holds the state with the key,
+    /// or the empty slot it belongs in.
+    fn slot(&self, key: Packed) -> usize {
+        let mask = self.index.len() - 1;
+        // Fibonacci hashing, taking the high bits which mix in all of the key.
+        let mut slot = (key.wrapping_mul(0x9e3779b97f4a7c15) >> (64 - self.index.len().trailing_zeros())) as usize;
+        while self.index[slot] != NO_STATE && self.keys[self.index[slot] as usize] != key {
+            slot = (slot + 1) & mask;
+        }
+        slot
+    }
+
+    /// Returns the moves which lead from the start to the state, along
+    /// with the board each is made on.
+    pub(crate) fn path(&self, id: u32, rules: &Rules) -> Result<Vec<(Board, Tool)>, SolveError> {
+        let mut steps = Vec::new();
+        let mut id = id;
+        while id != 0 {
+            let node = &self.nodes[id as usize];
+            steps.push((node.action, node.symmetry));
+            id = node.parent;
+        }
+        steps.reverse();
+        self.packer.replay(&steps, rules)
+    }
+
+    /// A rough estimate, in bytes, of the memory taken by the arena.
+    pub(crate) fn memory_estimate(&self) -> usize {
+        self.keys.capacity() * size_of::<Packed>()
+            + self.nodes.capacity() * size_of::<Node>()
+            + self.index.capacity() * size_of::<u32>()
+            + self.packer.memory_estimate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::catalog::Level;
+    use crate::solver::{solve_with, Progress, SearchObserver, SearchStats, SolveOptions};
+    use crate::tools::{Piece, Swap};
+
+    #[test]
+    fn test_pack() {
+        let swap = Tool::new(Swap::from_str("(##)").unwrap());
+        let board = Board::from_str("[1.9][0..][..3]").unwrap();
+        let mut arena = Arena::new(&board, vec![swap.clone()]);
+        let (unpacked, tools) = arena.packer.unpack(arena.key(0));
+        assert_eq!(unpacked, board);
+        assert_eq!(tools, [swap]);
+
+        let piece = Tool::new(Piece::from_str("[2]").unwrap());
+        let next = piece.apply(&board, &Rules::default()).0;
+        let key = arena.packer.pack(&next, 0);
+        assert_eq!(arena.insert(key, 0, &piece, Symmetry::IDENTITY), (1, true));
+        assert_eq!(arena.insert(key, 0, &piece, Symmetry::IDENTITY), (1, false));
+        assert_eq!(arena.packer.unpack(arena.key(1)).0, next);
+        assert_eq!(arena.path(1, &Rules::default()), Ok(vec![(board, piece)]));
+    }
+
+    #[test]
+    fn test_small_states() {
+        assert_eq!(size_of::<Node>(), 12);
+
+        /// Keeps the last report of the search.
+        #[derive(Default)]
+        struct Last(Mutex<Option<Progress>>);
+
+        impl SearchObserver for Last {
+            fn progress(&self, progress: &Progress) {
+                *self.0.lock().unwrap() = Some(progress.clone());
+            }
+
+            fn finished(&self, _stats: &SearchStats) {}
+        }
+
+        // the biggest search in the catalog.
+        let Level { puzzle, .. } = crate::catalog::level("large").unwrap();
+        let last = Arc::new(Last::default());
+        let options = SolveOptions { observer: Some(last.clone()), ..Default::default() };
+        solve_with(&puzzle.board, &puzzle.goal, &puzzle.tools, &options).unwrap();
+        let progress = last.0.lock().unwrap().clone().unwrap();
+        assert!(progress.visited > 10_000, "{progress:?}");
+        let per_state = progress.memory_estimate / progress.visited;
+        assert!(per_state <= 40, "{per_state} bytes per state");
+    }
+
+    #[test]
+    #[should_panic(expected = "must fit")]
+    fn test_cells_fit() {
+        let mut board = Board::from_str("[...][...][...]").unwrap();
+        board.cells_mut()[0] = Some(EMPTY_CELL as u8);
+        pack_board(&board);
+    }
+}
@@ -0,0 +1,105 @@
+use std::str::FromStr;
+use crate::puzzle::Puzzle;
+
+/// The puzzle files of the levels shipped with the crate, by id.
+///
+/// The game's levels have ids of the form `<world>-<level>`, and give
+/// the game's move count for them as `moves`. None have been transcribed
+/// yet. The other levels are puzzles kept as regression fixtures, each
+/// named after what it's there for. Every level should give the length
+/// of its shortest solution as `moves`. The tests check that against a
+/// plain breadth-first search, and hold the solver to it.
+const LEVELS: &[(&str, &str)] = &[
+    ("demo", include_str!("../levels/demo.txt")),
+    ("push-lift", include_str!("../levels/push-lift.txt")),
+    ("large", include_str!("../levels/large.txt")),
+];
+
+/// A level shipped with the crate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Level {
+    pub id: &'static str,
+    pub puzzle: Puzzle,
+}
+
+impl Level {
+    /// Where the level is found in the game, as its world and its
+    /// number within the world, or `None` for the regression fixtures.
+    pub fn place(&self) -> Option<(u32, u32)> {
+        let (world, level) = self.id.split_once('-')?;
+        Some((world.parse().ok()?, level.parse().ok()?))
+    }
+}
+
+/// Returns every level shipped with the crate, in order.
+pub fn catalog() -> Vec<Level> {
+    LEVELS.iter().map(|&(id, file)| parse_level(id, file)).collect()
+}
+
+/// Returns the level with the id, if there is one.
+pub fn level(id: &str) -> Option<Level> {
+    LEVELS.iter()
+        .find(|(level_id, _)| *level_id == id)
+        .map(|&(id, file)| parse_level(id, file))
+}
+
+fn parse_level(id: &'static str, file: &str) -> Level {
+    // the tests check every level parses.
+    let puzzle = Puzzle::from_str(file).unwrap_or_else(|err| panic!("level {id}: {err}"));
+    Level { id, puzzle }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::solver::{solutions, solve};
+    use crate::tools::Rules;
+
+    #[test]
+    fn test_ids() {
+        let ids: HashSet<_> = LEVELS.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids.len(), LEVELS.len());
+        assert_eq!(level("demo").unwrap().id, "demo");
+        assert_eq!(level("no-such-level"), None);
+        assert_eq!(level("push-lift").unwrap().place(), None);
+        let from_game = Level { id: "2-13", puzzle: level("demo").unwrap().puzzle };
+        assert_eq!(from_game.place(), Some((2, 13)));
+    }
+
+    #[test]
+    fn test_moves() {
+        // checked without the solver's shortcuts, so a mistake in them
+        // can't make its way into the levels.
+        for Level { id, puzzle } in catalog() {
+            let shortest = solutions(&puzzle.board, &puzzle.goal, &puzzle.tools, &Rules::default(), 1);
+            assert_eq!(shortest.first().map(Vec::len), puzzle.moves, "level {id}");
+        }
+    }
+
+    #[test]
+    fn test_solutions() {
+        for Level { id, puzzle } in catalog() {
+            let moves = puzzle.moves.unwrap_or_else(|| panic!("level {id} should give its moves"));
+            let rules = Rules::default();
+            let solution = solve(&puzzle.board, &puzzle.goal, &puzzle.tools, &rules)
+                .unwrap_or_else(|err| panic!("level {id}: {err}"));
+            assert_eq!(solution.len(), moves, "level {id}");
+
+            // replay the solution, using each tool once.
+            let mut board = puzzle.board.clone();
+            let mut tools = puzzle.tools.clone();
+            for (step_board, action) in solution {
+                assert_eq!(step_board, board, "level {id}");
+                let (next, produced) = action.apply(&board, &rules);
+                let idx = tools.iter()
+                    .position(|tool| tool.transformations().contains(&action))
+                    .unwrap_or_else(|| panic!("level {id}: {action:?} isn't one of the tools left"));
+                tools.remove(idx);
+                tools.extend(produced);
+                board = next;
+            }
+            assert_eq!(board, puzzle.goal, "level {id}");
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::mem::size_of;
+use std::time::Instant;
+use crate::solver::{canonical, over_budget, placements, states_per_sec, successors, Budget, GameState, Progress, SearchStats, SolveError, SolveOptions, REPORT_EVERY};
+use crate::tools::{Board, Tool};
+
+/// Solutions are only counted up to this many.
+const SOLUTION_CAP: usize = 100;
+
+/// Measurements of how hard a puzzle is, taken from exploring every
+/// state the puzzle can reach.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DifficultyReport {
+    /// Every distinct state reachable from the start.
+    pub states_explored: usize,
+    /// The average number of moves available from each state.
+    pub branching_factor: f64,
+    /// The length of the shortest solution, if there is one.
+    pub solution_depth: Option<usize>,
+    /// The number of distinct shortest solutions, up to 100.
+    pub solutions: usize,
+    /// States whose board is one cell away from the goal.
+    pub near_solutions: usize,
+    /// The fraction of states from which the goal can no longer be reached.
+    pub dead_end_ratio: f64,
+}
+
+impl DifficultyReport {
+    /// Combine the measurements into a single score, or `None` if the
+    /// puzzle can't be solved. Longer solutions count the most, then the
+    /// size of the search, how easy it is to go wrong, and how many
+    /// tempting near misses there are. Having several solutions to find
+    /// makes a puzzle easier.
+    pub fn score(&self) -> Option<f64> {
+        let depth = self.solution_depth? as f64;
+        let score = depth
+            + (self.states_explored as f64).log2() / 2.0
+            + 3.0 * self.dead_end_ratio
+            + (1.0 + self.near_solutions as f64).log2()
+            - (self.solutions as f64).log2();
+        Some(score.max(0.0))
+    }
+}
+
+impl fmt::Display for DifficultyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.score() {
+            Some(score) => writeln!(f, "difficulty:       {score:.1}")?,
+            None => writeln!(f, "difficulty:       unsolvable")?,
+        }
+        match self.solution_depth {
+            Some(depth) => writeln!(f, "solution depth:   {depth}")?,
+            None => writeln!(f, "solution depth:   -")?,
+        }
+        let cap = if self.solutions >= SOLUTION_CAP { "+" } else { "" };
+        writeln!(f, "solutions:        {}{cap}", self.solutions)?;
+        writeln!(f, "states explored:  {}", self.states_explored)?;
+        writeln!(f, "branching factor: {:.1}", self.branching_factor)?;
+        writeln!(f, "near solutions:   {}", self.near_solutions)?;
+        writeln!(f, "dead ends:        {:.0}%", self.dead_end_ratio * 100.0)
+    }
+}
+
+/// Explore every state reachable in the puzzle, and measure how hard
+/// it is to solve. Like `solve_with`, gives up if any of the limits in
+/// `options` run out, since puzzles with many tools can reach a great
+/// many states. The states are always kept in memory.
+pub fn analyze(board: &Board, goal: &Board, tools: &[Tool], options: &SolveOptions) -> Result<DifficultyReport, SolveError> {
+    let started = Instant::now();
+    let mut so_far = SearchStats::default();
+    let result = explore(board, goal, tools, options, started, &mut so_far);
+    if let Some(observer) = &options.observer {
+        so_far.elapsed = started.elapsed();
+        observer.finished(&so_far);
+    }
+    result
+}
+
+/// Keeps `so_far` up to date as it goes, for the observer.
+fn explore(
+    board: &Board,
+    goal: &Board,
+    tools: &[Tool],
+    options: &SolveOptions,
+    started: Instant,
+    so_far: &mut SearchStats,
+) -> Result<DifficultyReport, SolveError> {
+    let rules = &options.rules;
+    let start = (board.clone(), canonical(tools.to_vec()));
+    let mut ids = HashMap::new();
+    ids.insert(start.clone(), 0);
+    let mut states = vec![start];
+    let mut depths = vec![0];
+    let mut children: Vec<Vec<usize>> = Vec::new();
+    // the moves into each state from the layer before it, which make up
+    // its shortest paths.
+    let mut arrivals: Vec<Vec<(usize, Tool)>> = vec![Vec::new()];
+    let mut moves = 0;
+
+    // `states` doubles as the BFS queue.
+    let mut idx = 0;
+    while idx < states.len() {
+        let depth = depths[idx];
+        so_far.depth = depth;
+        if let Some(observer) = options.observer.as_ref().filter(|_| idx % REPORT_EVERY == 0) {
+            let elapsed = started.elapsed();
+            observer.progress(&Progress {
+                depth,
+                frontier: states.len() - idx,
+                visited: states.len(),
+                states_explored: idx,
+                memory_estimate: states.capacity() * size_of::<GameState>(),
+                states_per_sec: states_per_sec(idx, elapsed),
+                elapsed,
+            });
+        }
+        if let Some(err) = over_budget(options, started, idx, depth) {
+            return Err(err);
+        }
+        let next = successors(&states[idx], rules);
+        if options.max_depth.is_some_and(|max_depth| depth >= max_depth) && !next.is_empty() {
+            let stats = SearchStats { states_explored: idx, depth, elapsed: started.elapsed() };
+            return Err(SolveError::Timeout(Budget::Depth, stats));
+        }
+        idx += 1;
+        so_far.states_explored = idx;
+
+        let mut state_children = Vec::new();
+        for (action, next) in next {
+            moves += 1;
+            let child = match ids.get(&next) {
+                Some(&child) => child,
+                None => {
+                    ids.insert(next.clone(), states.len());
+                    states.push(next);
+                    depths.push(depth + 1);
+                    arrivals.push(Vec::new());
+                    states.len() - 1
+                }
+            };
+            if depths[child] == depth + 1 {
+                arrivals[child].push((idx - 1, action));
+            }
+            state_children.push(child);
+        }
+        children.push(state_children);
+    }
+
+    let is_goal: Vec<_> = states.iter().map(|(board, _)| board == goal).collect();
+    let solution_depth = (0..states.len()).filter(|&id| is_goal[id]).map(|id| depths[id]).min();
+
+    // walk back from the goals to find every state which can reach one.
+    let mut parents = vec![Vec::new(); states.len()];
+    for (id, state_children) in children.iter().enumerate() {
+        for &child in state_children {
+            parents[child].push(id);
+        }
+    }
+    let mut can_solve = is_goal.clone();
+    let mut stack: Vec<_> = (0..states.len()).filter(|&id| is_goal[id]).collect();
+    while let Some(id) = stack.pop() {
+        for &parent in &parents[id] {
+            if !can_solve[parent] {
+                can_solve[parent] = true;
+                stack.push(parent);
+            }
+        }
+    }
+
+    // count the shortest solutions as `solutions` does, ignoring the
+    // order the placements are made in.
+    let mut seen = HashSet::new();
+    let mut walked = HashSet::new();
+    for id in (0..states.len()).filter(|&id| is_goal[id] && Some(depths[id]) == solution_depth) {
+        let keep_going = walk_back(id, &arrivals, &mut Vec::new(), &mut walked, &mut |path| {
+            seen.insert(placements(path.iter().copied()));
+            seen.len() < SOLUTION_CAP
+        });
+        if !keep_going { break }
+    }
+
+    let near_solutions = states.iter()
+        .filter(|(board, _)| distance(board, goal) == 1)
+        .count();
+    Ok(DifficultyReport {
+        states_explored: states.len(),
+        branching_factor: moves as f64 / states.len() as f64,
+        solution_depth,
+        solutions: seen.len(),
+        near_solutions,
+        dead_end_ratio: can_solve.iter().filter(|solvable| !**solvable).count() as f64 / states.len() as f64,
+    })
+}
+
+/// Walk back the shortest paths from the start to the state, passing
+/// the moves of each to `visit`, until `visit` returns false. Returns
+/// whether to keep going.
+///
+/// Paths which only differ in the order of their placements are only
+/// walked once: a state reached with the same placements still to make
+/// as before, in `walked`, leads back to the same solutions as before.
+fn walk_back<'a>(
+    id: usize,
+    arrivals: &'a [Vec<(usize, Tool)>],
+    path: &mut Vec<&'a Tool>,
+    walked: &mut HashSet<(usize, Vec<String>)>,
+    visit: &mut impl FnMut(&[&'a Tool]) -> bool,
+) -> bool {
+    if !walked.insert((id, placements(path.iter().copied()))) {
+        return true;
+    }
+    if arrivals[id].is_empty() {
+        return visit(path);
+    }
+    for (parent, action) in &arrivals[id] {
+        path.push(action);
+        let keep_going = walk_back(*parent, arrivals, path, walked, visit);
+        path.pop();
+        if !keep_going { return false }
+    }
+    true
+}
+
+/// The number of cells which differ between the two boards.
+fn distance(board: &Board, goal: &Board) -> usize {
+    board.cells().iter().zip(goal.cells()).filter(|(a, b)| a != b).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::solver::solutions;
+    use crate::tools::{Piece, Rules, Swap};
+
+    #[test]
+    fn test_analyze() {
+        let board = Board::from_str("[111][111][112]").unwrap();
+        let goal = Board::from_str("[112][111][111]").unwrap();
+        let tools = vec![
+            Tool::new(Swap::from_str("(#.#)").unwrap()),
+            Tool::new(Piece::from_str("(1)").unwrap()),
+        ];
+        let report = analyze(&board, &goal, &tools, &SolveOptions::default()).unwrap();
+        assert_eq!(report.solution_depth, Some(1));
+        assert_eq!(report.solutions, 1);
+        assert!(report.dead_end_ratio > 0.0);
+        assert!(report.score().is_some());
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[211][111][111]").unwrap();
+        let tools = vec![Tool::new(Swap::from_str("(##)").unwrap())];
+        let report = analyze(&board, &goal, &tools, &SolveOptions::default()).unwrap();
+        assert_eq!(report.solution_depth, None);
+        assert_eq!(report.solutions, 0);
+        assert_eq!(report.dead_end_ratio, 1.0);
+        assert_eq!(report.score(), None);
+    }
+
+    #[test]
+    fn test_counts_solutions() {
+        // either piece will do, and a swap first changes nothing.
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[211][111][111]").unwrap();
+        let tools = vec![
+            Tool::new(Piece::from_str("(2)").unwrap()),
+            Tool::new(Piece::from_str("(2.)(.1)").unwrap()),
+            Tool::new(Swap::from_str("(##)").unwrap()),
+        ];
+        let report = analyze(&board, &goal, &tools, &SolveOptions::default()).unwrap();
+        let found = solutions(&board, &goal, &tools, &Rules::default(), SOLUTION_CAP);
+        assert_eq!(report.solution_depth, Some(1));
+        assert_eq!(report.solutions, found.len());
+        assert_eq!(report.solutions, 2);
+    }
+
+    #[test]
+    fn test_commuting_solutions() {
+        // the pieces may go down in any of 9! orders, which are all the
+        // same solution.
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[222][222][222]").unwrap();
+        let tools = vec![Tool::new(Piece::from_str("(2)").unwrap()); 9];
+        let report = analyze(&board, &goal, &tools, &SolveOptions::default()).unwrap();
+        assert_eq!(report.solution_depth, Some(9));
+        assert_eq!(report.solutions, 1);
+    }
+
+    #[test]
+    fn test_budget() {
+        let board = Board::from_str("[111][111][112]").unwrap();
+        let goal = Board::from_str("[112][111][111]").unwrap();
+        let tools = vec![
+            Tool::new(Swap::from_str("(#.#)").unwrap()),
+            Tool::new(Piece::from_str("(1)").unwrap()),
+        ];
+        let options = SolveOptions { max_states: Some(3), ..Default::default() };
+        let err = analyze(&board, &goal, &tools, &options).unwrap_err();
+        assert!(matches!(err, SolveError::Timeout(Budget::States, SearchStats { states_explored: 3, .. })), "{err:?}");
+        let options = SolveOptions { max_depth: Some(1), ..Default::default() };
+        let err = analyze(&board, &goal, &tools, &options).unwrap_err();
+        assert!(matches!(err, SolveError::Timeout(Budget::Depth, _)), "{err:?}");
+    }
+}
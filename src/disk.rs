@@ -0,0 +1,402 @@
+use std::collections::{BinaryHeap, HashSet};
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use crate::arena::{pack_board, Packed, Packer};
+use crate::feasibility::Finding;
+use crate::solver::{canonical, over_budget, states_per_sec, track_closest, unsolvable, Budget, Progress, SearchStats, SolveError, SolveOptions, REPORT_EVERY};
+use crate::tools::{canonical_board, Board, Symmetry, Tool};
+
+/// How many states are sorted in memory before being written out as a run.
+const RUN_LEN: usize = 1 << 20;
+
+/// A state found by the search, and how it was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Record {
+    key: Packed,
+    parent: Packed,
+    action: u32,
+    symmetry: Symmetry,
+}
+
+impl Record {
+    const SIZE: usize = 8 + 8 + 4 + 1;
+
+    fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(&self.key.to_le_bytes())?;
+        out.write_all(&self.parent.to_le_bytes())?;
+        out.write_all(&self.action.to_le_bytes())?;
+        out.write_all(&[self.symmetry.index()])
+    }
+
+    /// Returns `None` at the end of the file.
+    fn read(input: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut bytes = [0; Self::SIZE];
+        match input.read_exact(&mut bytes) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+        Ok(Some(Record {
+            key: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            parent: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+            action: u32::from_le_bytes(bytes[16..20].try_into().unwrap()),
+            symmetry: Symmetry::from_index(bytes[20]),
+        }))
+    }
+}
+
+/// Reads the records of a file one at a time.
+struct Records(BufReader<File>);
+
+impl Records {
+    fn open(path: &Path) -> io::Result<Self> {
+        Ok(Records(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl Iterator for Records {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Record::read(&mut self.0).transpose()
+    }
+}
+
+/// A directory of the search's files, removed once the search is over.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(parent: &Path) -> io::Result<Self> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos());
+        let path = parent.join(format!("inbento-solver-{}-{nanos}", process::id()));
+        fs::create_dir_all(&path)?;
+        Ok(TempDir(path))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Like the search in `solve_with`, but keeping the states found in
+/// files under `dir` rather than in memory.
+///
+/// Each layer's states are written out in sorted runs as they're found,
+/// with duplicates only removed afterwards, by merging the runs together
+/// and against the sorted file of every state visited before (delayed
+/// duplicate detection). Memory use is then bounded by the length of a
+/// run. Commuting moves aren't pruned, since that needs every way into
+/// a state to be known while exploring it.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn search(
+    board: &Board,
+    goal: &Board,
+    tools: &[Tool],
+    options: &SolveOptions,
+    dir: &Path,
+    findings: Vec<Finding>,
+    started: Instant,
+    so_far: &mut SearchStats,
+) -> Result<Vec<(Board, Tool)>, SolveError> {
+    search_with_runs(board, goal, tools, options, dir, findings, started, so_far, RUN_LEN)
+        .unwrap_or_else(|err| Err(SolveError::Io(err.to_string())))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search_with_runs(
+    board: &Board,
+    goal: &Board,
+    tools: &[Tool],
+    options: &SolveOptions,
+    dir: &Path,
+    findings: Vec<Finding>,
+    started: Instant,
+    so_far: &mut SearchStats,
+    run_len: usize,
+) -> io::Result<Result<Vec<(Board, Tool)>, SolveError>> {
+    let rules = &options.rules;
+    let goal_counts = goal.ingredient_counts();
+    let goal_bits = pack_board(goal);
+    let symmetries = crate::tools::symmetries(goal, tools);
+    let dir = TempDir::new(dir)?;
+    let layer_path = |depth: usize| dir.0.join(format!("layer-{depth}"));
+    let visited_path = |depth: usize| dir.0.join(format!("visited-{depth}"));
+
+    let mut packer = Packer::new(board);
+    let toolset = packer.toolset(canonical(tools.to_vec()));
+    let start = packer.pack(board, toolset);
+    let root = Record { key: start, parent: start, action: 0, symmetry: Symmetry::IDENTITY };
+    write_records(&layer_path(0), [root])?;
+    write_records(&visited_path(0), [root])?;
+    let mut visited = 1;
+
+    let mut depth = 0;
+    let mut layer_len = 1;
+    let mut states_explored = 0;
+    let mut closest = None;
+    while layer_len > 0 {
+        so_far.depth = depth;
+        if options.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            for record in Records::open(&layer_path(depth))? {
+                let record = record?;
+                if Packer::board_bits(record.key) == goal_bits {
+                    return rebuild_path(&packer, &layer_path, depth, record, rules);
+                }
+            }
+            // it's only a real limit if there were moves left to make.
+            for record in Records::open(&layer_path(depth))? {
+                if !packer.tools(record?.key).is_empty() {
+                    let stats = SearchStats { states_explored: states_explored + layer_len, depth, elapsed: started.elapsed() };
+                    return Ok(Err(SolveError::Timeout(Budget::Depth, stats)));
+                }
+            }
+        }
+
+        let mut runs = Vec::new();
+        let mut found = Vec::new();
+        for (idx, record) in Records::open(&layer_path(depth))?.enumerate() {
+            let record = record?;
+            if let Some(observer) = options.observer.as_ref().filter(|_| idx == 0 || states_explored % REPORT_EVERY == 0) {
+                let elapsed = started.elapsed();
+                observer.progress(&Progress {
+                    depth,
+                    frontier: layer_len - idx + runs.len() * run_len + found.len(),
+                    visited,
+                    states_explored,
+                    memory_estimate: found.capacity() * size_of::<Record>() + packer.memory_estimate(),
+                    states_per_sec: states_per_sec(states_explored, elapsed),
+                    elapsed,
+                });
+            }
+            if let Some(err) = over_budget(options, started, states_explored, depth) {
+                return Ok(Err(err));
+            }
+            states_explored += 1;
+            so_far.states_explored = states_explored;
+            if Packer::board_bits(record.key) == goal_bits {
+                return rebuild_path(&packer, &layer_path, depth, record, rules);
+            }
+            let (board, tools) = packer.unpack(record.key);
+            let tools = tools.to_vec();
+            track_closest(&mut closest, &board, &goal_counts);
+
+            let mut tried = HashSet::new();
+            for (idx, tool) in tools.iter().enumerate() {
+                if !tried.insert(tool) { continue }
+                for action in tool.transformations() {
+                    let (next_board, produced) = action.apply(&board, rules);
+                    let (next_board, symmetry) = canonical_board(&next_board, &symmetries);
+                    let mut next_tools = tools.clone();
+                    next_tools.remove(idx);
+                    next_tools.extend(produced);
+                    let next_tools = packer.toolset(canonical(next_tools));
+                    let key = packer.pack(&next_board, next_tools);
+                    let action = packer.action(&action);
+                    found.push(Record { key, parent: record.key, action, symmetry });
+                    if found.len() >= run_len {
+                        runs.push(write_run(&dir.0, runs.len(), &mut found)?);
+                    }
+                }
+            }
+        }
+        if !found.is_empty() {
+            runs.push(write_run(&dir.0, runs.len(), &mut found)?);
+        }
+
+        // merge the runs, dropping duplicates and anything visited before.
+        let mut merged = Merge::new(&runs)?;
+        let mut before = Records::open(&visited_path(depth))?.peekable();
+        let mut layer = BufWriter::new(File::create(layer_path(depth + 1))?);
+        let mut all = BufWriter::new(File::create(visited_path(depth + 1))?);
+        layer_len = 0;
+        let mut last = None;
+        while let Some(record) = merged.next()? {
+            if last == Some(record.key) { continue }
+            last = Some(record.key);
+            while let Some(old) = before.next_if(|old| old.as_ref().is_ok_and(|old| old.key < record.key)) {
+                old?.write(&mut all)?;
+            }
+            if before.peek().is_some_and(|old| old.as_ref().is_ok_and(|old| old.key == record.key)) {
+                continue;
+            }
+            record.write(&mut layer)?;
+            record.write(&mut all)?;
+            layer_len += 1;
+        }
+        for old in before {
+            old?.write(&mut all)?;
+        }
+        layer.flush()?;
+        all.flush()?;
+        for run in runs {
+            fs::remove_file(run)?;
+        }
+        fs::remove_file(visited_path(depth))?;
+        visited += layer_len;
+        depth += 1;
+    }
+    Ok(Err(unsolvable(states_explored, findings, goal_counts, closest)))
+}
+
+fn write_records(path: &Path, records: impl IntoIterator<Item=Record>) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for record in records {
+        record.write(&mut out)?;
+    }
+    out.flush()
+}
+
+/// Sort and write out the records, emptying them.
+fn write_run(dir: &Path, number: usize, records: &mut Vec<Record>) -> io::Result<PathBuf> {
+    records.sort_by_key(|record| record.key);
+    records.dedup_by_key(|record| record.key);
+    let path = dir.join(format!("run-{number}"));
+    write_records(&path, records.drain(..))?;
+    Ok(path)
+}
+
+/// Merges sorted runs into one sorted sequence of records.
+struct Merge {
+    runs: Vec<Records>,
+    heads: BinaryHeap<Reverse<(Packed, usize)>>,
+    pending: Vec<Option<Record>>,
+}
+
+impl Merge {
+    fn new(paths: &[PathBuf]) -> io::Result<Self> {
+        let mut merge = Merge { runs: Vec::new(), heads: BinaryHeap::new(), pending: Vec::new() };
+        for path in paths {
+            merge.runs.push(Records::open(path)?);
+            merge.pending.push(None);
+            merge.advance(merge.runs.len() - 1)?;
+        }
+        Ok(merge)
+    }
+
+    fn advance(&mut self, run: usize) -> io::Result<()> {
+        if let Some(record) = self.runs[run].next().transpose()? {
+            self.heads.push(Reverse((record.key, run)));
+            self.pending[run] = Some(record);
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> io::Result<Option<Record>> {
+        let Some(Reverse((_, run))) = self.heads.pop() else { return Ok(None) };
+        let record = self.pending[run].take();
+        self.advance(run)?;
+        Ok(record)
+    }
+}
+
+/// Walk back from the record through the layer files to the start, and
+/// replay the moves found.
+fn rebuild_path(
+    packer: &Packer,
+    layer_path: &impl Fn(usize) -> PathBuf,
+    depth: usize,
+    record: Record,
+    rules: &crate::tools::Rules,
+) -> io::Result<Result<Vec<(Board, Tool)>, SolveError>> {
+    let mut steps = Vec::new();
+    let mut record = record;
+    for depth in (0..depth).rev() {
+        steps.push((record.action, record.symmetry));
+        let parent = record.parent;
+        record = Records::open(&layer_path(depth))?
+            .find(|found| found.as_ref().map_or(true, |found| found.key == parent))
+            .expect("every state's parent is in the layer before it")?;
+    }
+    steps.reverse();
+    Ok(packer.replay(&steps, rules))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::solver::{solve, solve_with};
+    use crate::tools::{Piece, Push, Rules, Swap};
+
+    fn demo() -> (Board, Board, Vec<Tool>) {
+        let board = Board::from_str("[131][111][113]").unwrap();
+        let goal = Board::from_str("[211][121][112]").unwrap();
+        let tools = vec![
+            Tool::new(Piece::from_str("(22)(.2)").unwrap()),
+            Tool::new(Swap::from_str("(#.#)").unwrap()),
+            Tool::new(Swap::from_str("(##)").unwrap()),
+            Tool::new(Push::from_str("(v<)").unwrap()),
+        ];
+        (board, goal, tools)
+    }
+
+    #[test]
+    fn test_matches_memory() {
+        let (board, goal, tools) = demo();
+        let dir = std::env::temp_dir();
+        // tiny runs, so that there's plenty of merging to do.
+        let solution = search_with_runs(&board, &goal, &tools, &SolveOptions::default(), &dir, Vec::new(), Instant::now(), &mut SearchStats::default(), 100)
+            .unwrap()
+            .unwrap();
+        assert_eq!(solution.len(), solve(&board, &goal, &tools, &Rules::default()).unwrap().len());
+        let mut replayed = board;
+        for (board, tool) in solution {
+            assert_eq!(replayed, board);
+            replayed = tool.apply(&board, &Rules::default()).0;
+        }
+        assert_eq!(replayed, goal);
+    }
+
+    #[test]
+    fn test_goal_at_max_depth() {
+        // the goal is only one of the states a move away, and not the
+        // first found.
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[111][111][112]").unwrap();
+        let tools = vec![
+            Tool::new(Piece::from_str("(2)").unwrap()),
+            Tool::new(Swap::from_str("(##)").unwrap()),
+        ];
+        let options = SolveOptions { max_depth: Some(1), ..Default::default() };
+        let dir = std::env::temp_dir();
+        let solution = search_with_runs(&board, &goal, &tools, &options, &dir, Vec::new(), Instant::now(), &mut SearchStats::default(), 10)
+            .unwrap()
+            .unwrap();
+        assert_eq!(Ok(solution), solve_with(&board, &goal, &tools, &options));
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        let board = Board::from_str("[221][121][112]").unwrap();
+        let goal = Board::from_str("[121][212][121]").unwrap();
+        let tools = vec![
+            Tool::new(Swap::from_str("(##)").unwrap()),
+            Tool::new(Swap::from_str("(##)").unwrap()),
+            Tool::new(Swap::from_str("(#.#)").unwrap()),
+        ];
+        let dir = std::env::temp_dir();
+        let err = search_with_runs(&board, &goal, &tools, &SolveOptions::default(), &dir, Vec::new(), Instant::now(), &mut SearchStats::default(), 10)
+            .unwrap()
+            .unwrap_err();
+        let SolveError::Unsolvable(expected) = solve(&board, &goal, &tools, &Rules::default()).unwrap_err() else { panic!() };
+        let SolveError::Unsolvable(err) = err else { panic!("{err:?}") };
+        assert_eq!(err.closest_counts, expected.closest_counts);
+    }
+
+    #[test]
+    fn test_records() {
+        let record = Record { key: 1 << 40 | 5, parent: 7, action: 3, symmetry: Symmetry::from_index(6) };
+        let mut bytes = Vec::new();
+        record.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), Record::SIZE);
+        assert_eq!(Record::read(&mut &bytes[..]).unwrap(), Some(record));
+        assert_eq!(Record::read(&mut &[][..]).unwrap(), None);
+    }
+}
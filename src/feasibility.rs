@@ -0,0 +1,186 @@
+use std::fmt;
+use crate::tools::{Board, CellMask, Rules, Tool, AREA};
+
+/// How much a finding matters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Worth knowing, but the puzzle may still be solved.
+    Warning,
+    /// The puzzle can't be solved.
+    Fatal,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Fatal => write!(f, "fatal"),
+        }
+    }
+}
+
+/// Something noticed about a puzzle without searching it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Finding {
+    /// The board already matches the goal.
+    AlreadySolved,
+    /// Cells which differ from the goal, but which no tool can ever reach.
+    UntouchableCells(CellMask),
+    /// The goal needs more of an ingredient than the tools could ever
+    /// put on the board.
+    TooFewIngredient { ingredient: u8, goal: u8, most: u8 },
+    /// The goal needs less of an ingredient than the tools could ever
+    /// take off the board.
+    TooManyIngredient { ingredient: u8, goal: u8, least: u8 },
+}
+
+impl Finding {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Finding::AlreadySolved => Severity::Warning,
+            Finding::UntouchableCells(_)
+            | Finding::TooFewIngredient { .. }
+            | Finding::TooManyIngredient { .. } => Severity::Fatal,
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Finding::AlreadySolved => write!(f, "the board already matches the goal"),
+            Finding::UntouchableCells(cells) =>
+                write!(f, "no tool can reach these cells, which differ from the goal: {cells:?}"),
+            Finding::TooFewIngredient { ingredient, goal, most } =>
+                write!(f, "the goal has {goal}×{ingredient}, but the board can have at most {most}"),
+            Finding::TooManyIngredient { ingredient, goal, least } =>
+                write!(f, "the goal has {goal}×{ingredient}, but the board will always have at least {least}"),
+        }
+    }
+}
+
+/// Check for anything which can be told about the puzzle without
+/// searching it. These are only necessary conditions: a puzzle with no
+/// fatal findings may still be unsolvable.
+pub fn check(board: &Board, goal: &Board, tools: &[Tool], rules: &Rules) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if board == goal {
+        findings.push(Finding::AlreadySolved);
+    }
+
+    let untouchable = untouchable_cells(board, goal, tools, rules);
+    if !untouchable.is_empty() {
+        findings.push(Finding::UntouchableCells(untouchable));
+    }
+
+    // tools which produce more tools (like `Lift`) say so in their own
+    // effect, so the tools we start with are enough to bound the counts.
+    let effects: Vec<_> = tools.iter().map(|tool| tool.food_effect(rules)).collect();
+    let copies: u32 = effects.iter().map(|effect| effect.copies as u32).sum();
+    let removes = effects.iter().any(|effect| effect.removes);
+    let goal_counts = goal.ingredient_counts();
+    for (ingredient, count) in board.ingredient_counts().into_iter().enumerate() {
+        let goal = goal_counts[ingredient];
+        let adds: u32 = effects.iter().map(|effect| effect.adds[ingredient] as u32).sum();
+        let mut most = count as u32 + adds;
+        // food can only be copied if there's some to copy.
+        if most > 0 {
+            most += copies;
+        }
+        let most = most.min(AREA as u32) as u8;
+        if goal > most {
+            findings.push(Finding::TooFewIngredient { ingredient: ingredient as u8, goal, most });
+        }
+        let least = if removes { 0 } else { count };
+        if goal < least {
+            findings.push(Finding::TooManyIngredient { ingredient: ingredient as u8, goal, least });
+        }
+    }
+    findings
+}
+
+/// Returns the cells which differ between the board and the goal, but
+/// which no tool can reach.
+fn untouchable_cells(board: &Board, goal: &Board, tools: &[Tool], rules: &Rules) -> CellMask {
+    let reach = tools.iter()
+        .map(|tool| tool.reach(rules))
+        .fold(CellMask::EMPTY, CellMask::union);
+    (0..AREA)
+        .filter(|&idx| board.cells()[idx] != goal.cells()[idx] && !reach.contains(idx))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::tools::{CopyPaste, Lift, Piece, Push, PushRule, Swap};
+
+    fn fatal(findings: &[Finding]) -> Vec<&Finding> {
+        findings.iter().filter(|finding| finding.severity() == Severity::Fatal).collect()
+    }
+
+    #[test]
+    fn test_already_solved() {
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let findings = check(&board, &board, &[], &Rules::default());
+        assert_eq!(findings, vec![Finding::AlreadySolved]);
+    }
+
+    #[test]
+    fn test_untouchable() {
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[211][111][111]").unwrap();
+        let tools = vec![Tool::new(Piece::from_str("[...][.2.][...]").unwrap())];
+        let findings = check(&board, &goal, &tools, &Rules::default());
+        assert_eq!(findings, vec![Finding::UntouchableCells([0].into_iter().collect())]);
+    }
+
+    #[test]
+    fn test_too_few() {
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[222][111][111]").unwrap();
+        let tools = vec![Tool::new(Piece::from_str("(22)").unwrap())];
+        let findings = check(&board, &goal, &tools, &Rules::default());
+        assert_eq!(fatal(&findings), vec![&Finding::TooFewIngredient { ingredient: 2, goal: 3, most: 2 }]);
+    }
+
+    #[test]
+    fn test_copies() {
+        let board = Board::from_str("[111][111][112]").unwrap();
+        let goal = Board::from_str("[222][111][111]").unwrap();
+        let tools = vec![Tool::new(CopyPaste::from_str("(CVV)").unwrap())];
+        assert!(fatal(&check(&board, &goal, &tools, &Rules::default())).is_empty());
+        // nothing to copy.
+        let board = Board::from_str("[111][111][111]").unwrap();
+        assert!(!fatal(&check(&board, &goal, &tools, &Rules::default())).is_empty());
+    }
+
+    #[test]
+    fn test_too_many() {
+        let board = Board::from_str("[211][111][111]").unwrap();
+        let goal = Board::from_str("[111][111][111]").unwrap();
+        let swap = Tool::new(Swap::from_str("(##)").unwrap());
+        let findings = check(&board, &goal, &[swap], &Rules::default());
+        assert_eq!(fatal(&findings), vec![
+            &Finding::TooFewIngredient { ingredient: 1, goal: 9, most: 8 },
+            &Finding::TooManyIngredient { ingredient: 2, goal: 0, least: 1 },
+        ]);
+        // lifting the 2 off is fine, but that still doesn't make a 1.
+        let lift = Tool::new(Lift::from_str("(#)").unwrap());
+        assert_eq!(fatal(&check(&board, &goal, &[lift], &Rules::default())).len(), 1);
+    }
+
+    #[test]
+    fn test_push_rules() {
+        let board = Board::from_str("[21.][111][111]").unwrap();
+        let goal = Board::from_str("[11.][111][111]").unwrap();
+        let push = Tool::new(Push::from_str("(<)").unwrap());
+        let rules = Rules::default();
+        assert!(fatal(&check(&board, &goal, std::slice::from_ref(&push), &rules)).iter()
+            .all(|finding| !matches!(finding, Finding::TooManyIngredient { .. })));
+        let rules = Rules { push: PushRule::Shove };
+        assert!(fatal(&check(&board, &goal, &[push], &rules)).iter()
+            .any(|finding| matches!(finding, Finding::TooManyIngredient { .. })));
+    }
+}
@@ -0,0 +1,250 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::puzzle::Puzzle;
+use crate::solver::solutions;
+use crate::tools::{Board, Registry, Rules, Tool, SIZE};
+
+/// A small seedable pseudorandom number generator (SplitMix64), so that
+/// generated puzzles can be reproduced from their seed.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`, which mustn't be empty.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub(crate) fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for idx in (1..items.len()).rev() {
+            items.swap(idx, self.below(idx + 1));
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GeneratorOptions {
+    /// Puzzles generated from the same seed (and options) are the same.
+    pub seed: u64,
+    /// How many tools the puzzle gives.
+    pub tools: usize,
+    /// How many different ingredients may appear on the board, up to 9.
+    pub ingredients: u8,
+    /// The names of the kinds of tool to draw from, which must be
+    /// built-in kinds.
+    pub kinds: Vec<&'static str>,
+    /// How many candidate puzzles to try before giving up.
+    pub attempts: usize,
+    pub rules: Rules,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            seed: 0,
+            tools: 3,
+            ingredients: 3,
+            kinds: Registry::default().names().collect(),
+            attempts: 1000,
+            rules: Rules::default(),
+        }
+    }
+}
+
+/// Why `generate` didn't return a puzzle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GeneratorError {
+    /// The options can't make a puzzle.
+    InvalidOptions(&'static str),
+    /// No candidate passed within `options.attempts`.
+    GaveUp,
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeneratorError::InvalidOptions(err) => write!(f, "{err}"),
+            GeneratorError::GaveUp => write!(f, "could not generate a puzzle; try another seed"),
+        }
+    }
+}
+
+/// Generate a puzzle with a unique shortest solution, which uses every
+/// tool. Candidates are made by using each tool on a random board, in a
+/// random order, to find the goal; the solver then rejects any candidate
+/// which can be solved in fewer moves, or in more than one way.
+pub fn generate(options: &GeneratorOptions) -> Result<Puzzle, GeneratorError> {
+    if options.tools == 0 {
+        return Err(GeneratorError::InvalidOptions("puzzles need at least one tool"));
+    }
+    if options.kinds.is_empty() {
+        return Err(GeneratorError::InvalidOptions("there are no kinds of tool to draw from"));
+    }
+    if !options.kinds.iter().all(|kind| Registry::default().names().any(|name| name == *kind)) {
+        return Err(GeneratorError::InvalidOptions("can only generate the built-in kinds of tool"));
+    }
+    let mut rng = Rng::new(options.seed);
+    for _ in 0..options.attempts {
+        let Some(puzzle) = candidate(&mut rng, options) else { continue };
+        let found = solutions(&puzzle.board, &puzzle.goal, &puzzle.tools, &options.rules, 2);
+        if let [solution] = &found[..] {
+            if Some(solution.len()) == puzzle.moves {
+                return Ok(puzzle);
+            }
+        }
+    }
+    Err(GeneratorError::GaveUp)
+}
+
+fn candidate(rng: &mut Rng, options: &GeneratorOptions) -> Option<Puzzle> {
+    let cells: Vec<_> = (0..SIZE * SIZE).map(|_| Some(ingredient(rng, options))).collect();
+    let board = Board::from_str(&figure('[', SIZE, &cells)).ok()?;
+    let tools: Vec<_> = (0..options.tools)
+        .map(|_| random_tool(rng, options))
+        .collect::<Option<_>>()?;
+
+    // use every tool (and every tool they produce) to find the goal.
+    let mut pending = tools.clone();
+    rng.shuffle(&mut pending);
+    let mut goal = board.clone();
+    let mut moves = 0;
+    while let Some(tool) = pending.pop() {
+        let placements = tool.transformations();
+        let (next_goal, produced) = rng.pick(&placements).apply(&goal, &options.rules);
+        goal = next_goal;
+        moves += 1;
+        for tool in produced {
+            let idx = rng.below(pending.len() + 1);
+            pending.insert(idx, tool);
+        }
+    }
+    (goal != board).then_some(Puzzle { board, goal, tools, moves: Some(moves) })
+}
+
+fn ingredient(rng: &mut Rng, options: &GeneratorOptions) -> char {
+    let ingredient = 1 + rng.below(options.ingredients.clamp(1, 9) as usize);
+    char::from_digit(ingredient as u32, 10).unwrap()
+}
+
+fn random_tool(rng: &mut Rng, options: &GeneratorOptions) -> Option<Tool> {
+    let name = *rng.pick(&options.kinds);
+    let string = match name {
+        "copy" => {
+            let (width, mut cells) = region(rng, 2, 3);
+            let mut marks = vec!['C'];
+            marks.resize(cells.iter().flatten().count(), 'V');
+            rng.shuffle(&mut marks);
+            let mut marks = marks.into_iter();
+            cells.iter_mut().flatten().for_each(|cell| *cell = marks.next().unwrap());
+            figure('(', width, &cells)
+        }
+        "cycle" => {
+            let (width, mut cells) = region(rng, 3, 4);
+            let mut numbers: Vec<_> = (1..=cells.iter().flatten().count() as u32).collect();
+            rng.shuffle(&mut numbers);
+            let mut numbers = numbers.into_iter();
+            cells.iter_mut().flatten()
+                .for_each(|cell| *cell = char::from_digit(numbers.next().unwrap(), 10).unwrap());
+            figure('(', width, &cells)
+        }
+        "lift" => {
+            let (width, cells) = region(rng, 1, 3);
+            figure('(', width, &cells)
+        }
+        "piece" => {
+            let (width, mut cells) = region(rng, 1, 3);
+            cells.iter_mut().flatten().for_each(|cell| *cell = ingredient(rng, options));
+            figure('(', width, &cells)
+        }
+        "push" | "slide" => {
+            let (width, mut cells) = region(rng, 1, 3);
+            cells.iter_mut().flatten().for_each(|cell| *cell = *rng.pick(&['^', '>', 'v', '<']));
+            figure('(', width, &cells)
+        }
+        "rotate" => rng.pick(&["(RR)(RR)", "(LL)(LL)"]).to_string(),
+        "swap" => {
+            let (width, cells) = region(rng, 2, 2);
+            figure('(', width, &cells)
+        }
+        _ => return None,
+    };
+    Tool::parse(name, &string).ok()
+}
+
+/// Returns a random region between `min` and `max` cells, as its width
+/// and its rows of cells, with `#` marking the cells of the region.
+/// The region fills its bounding box, so no edge row or column is empty.
+fn region(rng: &mut Rng, min: usize, max: usize) -> (usize, Vec<Option<char>>) {
+    loop {
+        let width = 1 + rng.below(SIZE);
+        let height = 1 + rng.below(SIZE);
+        let cells: Vec<_> = (0..width * height)
+            .map(|_| (rng.below(2) == 0).then_some('#'))
+            .collect();
+        let count = cells.iter().flatten().count();
+        let row_filled = |y: usize| (0..width).any(|x| cells[y * width + x].is_some());
+        let column_filled = |x: usize| (0..height).any(|y| cells[y * width + x].is_some());
+        if (min..=max).contains(&count)
+            && row_filled(0) && row_filled(height - 1)
+            && column_filled(0) && column_filled(width - 1) {
+            return (width, cells);
+        }
+    }
+}
+
+/// Write out rows of cells in figure notation, wrapped in `open`.
+fn figure(open: char, width: usize, cells: &[Option<char>]) -> String {
+    let close = if open == '(' { ')' } else { ']' };
+    cells.chunks(width)
+        .map(|row| {
+            let row: String = row.iter().map(|cell| cell.unwrap_or('.')).collect();
+            format!("{open}{row}{close}")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deterministic() {
+        let options = GeneratorOptions { seed: 7, tools: 2, ..Default::default() };
+        assert_eq!(generate(&options), generate(&options));
+    }
+
+    #[test]
+    fn test_unique_solution() {
+        let options = GeneratorOptions { seed: 11, tools: 2, ..Default::default() };
+        let puzzle = generate(&options).expect("could not generate a puzzle");
+        let found = solutions(&puzzle.board, &puzzle.goal, &puzzle.tools, &options.rules, 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(Some(found[0].len()), puzzle.moves);
+        assert_eq!(puzzle.tools.len(), options.tools);
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let options = GeneratorOptions { kinds: Vec::new(), ..Default::default() };
+        assert!(matches!(generate(&options), Err(GeneratorError::InvalidOptions(_))));
+        let options = GeneratorOptions { kinds: vec!["bogus"], ..Default::default() };
+        assert!(matches!(generate(&options), Err(GeneratorError::InvalidOptions(_))));
+        let options = GeneratorOptions { tools: 0, ..Default::default() };
+        assert!(matches!(generate(&options), Err(GeneratorError::InvalidOptions(_))));
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+use crate::solver::solve;
+use crate::tools::{Board, CellMask, Rules, Tool, SIZE};
+
+/// How much a hint should give away.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HintLevel {
+    /// Which tool to use next.
+    Tool,
+    /// Which tool to use next, and roughly where.
+    Area,
+    /// Exactly where to place the next tool.
+    Placement,
+}
+
+/// The next move of a shortest solution, given away to some `HintLevel`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// The board already matches the goal.
+    Solved,
+    /// The goal can't be reached from here with the tools remaining.
+    Unsolvable,
+    Tool(Tool),
+    Area(Tool, Area),
+    /// The tool, and the tool as it should be placed.
+    Placement(Tool, Tool),
+}
+
+/// A rough position on the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Area {
+    /// 0, 1 or 2, for the top, middle or bottom.
+    pub row: usize,
+    /// 0, 1 or 2, for the left, middle or right.
+    pub column: usize,
+}
+
+impl Area {
+    /// The position at the middle of the cells.
+    fn of(mask: CellMask) -> Self {
+        // doubled, so that we can find the middle without rounding.
+        let span = |coords: Vec<usize>| {
+            let doubled = coords.iter().min().unwrap_or(&1) + coords.iter().max().unwrap_or(&1);
+            match doubled {
+                0 | 1 => 0,
+                2 => 1,
+                _ => 2,
+            }
+        };
+        Area {
+            row: span(mask.iter().map(|idx| idx / SIZE).collect()),
+            column: span(mask.iter().map(|idx| idx % SIZE).collect()),
+        }
+    }
+}
+
+impl fmt::Display for Area {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let row = ["top", "middle", "bottom"][self.row];
+        let column = ["left", "middle", "right"][self.column];
+        match (self.row, self.column) {
+            (1, 1) => write!(f, "center"),
+            (1, _) => write!(f, "{column}"),
+            (_, 1) => write!(f, "{row}"),
+            _ => write!(f, "{row} {column}"),
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Hint::Solved => write!(f, "already solved"),
+            Hint::Unsolvable => write!(f, "unsolvable from here"),
+            Hint::Tool(tool) => write!(f, "use the {} {}", tool.name(), tool.kind()),
+            Hint::Area(tool, area) => write!(f, "use the {} {} at the {area}", tool.name(), tool.kind()),
+            Hint::Placement(tool, placed) => {
+                write!(f, "use the {} {} like so: {}", tool.name(), tool.kind(), placed.kind())
+            }
+        }
+    }
+}
+
+/// Find the next move of a shortest solution from the current board
+/// and remaining tools, and give it away to the given `level`.
+pub fn hint(board: &Board, goal: &Board, tools: &[Tool], rules: &Rules, level: HintLevel) -> Hint {
+    let Ok(solution) = solve(board, goal, tools, rules) else { return Hint::Unsolvable };
+    let Some((_, placed)) = solution.into_iter().next() else { return Hint::Solved };
+    let tool = tools.iter()
+        .find(|tool| tool.transformations().contains(&placed))
+        .expect("solution used a tool we don't have")
+        .clone();
+    match level {
+        HintLevel::Tool => Hint::Tool(tool),
+        HintLevel::Area => {
+            let area = Area::of(placed.footprint(rules));
+            Hint::Area(tool, area)
+        }
+        HintLevel::Placement => Hint::Placement(tool, placed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::tools::{Piece, Swap};
+
+    fn puzzle() -> (Board, Board, Vec<Tool>) {
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[111][111][122]").unwrap();
+        let tools = vec![
+            Tool::new(Swap::from_str("(##)").unwrap()),
+            Tool::new(Piece::from_str("(22)").unwrap()),
+        ];
+        (board, goal, tools)
+    }
+
+    #[test]
+    fn test_hint_levels() {
+        let (board, goal, tools) = puzzle();
+        let rules = Rules::default();
+        assert_eq!(hint(&board, &goal, &tools, &rules, HintLevel::Tool), Hint::Tool(tools[1].clone()));
+        assert_eq!(
+            hint(&board, &goal, &tools, &rules, HintLevel::Area),
+            Hint::Area(tools[1].clone(), Area { row: 2, column: 2 }),
+        );
+        assert_eq!(
+            hint(&board, &goal, &tools, &rules, HintLevel::Placement),
+            Hint::Placement(tools[1].clone(), Tool::new(Piece::from_str("(...)(...)(.22)").unwrap())),
+        );
+    }
+
+    #[test]
+    fn test_solved() {
+        let (_, goal, tools) = puzzle();
+        assert_eq!(hint(&goal, &goal, &tools, &Rules::default(), HintLevel::Tool), Hint::Solved);
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        let (board, goal, tools) = puzzle();
+        let hint = hint(&board, &goal, &tools[..1], &Rules::default(), HintLevel::Tool);
+        assert_eq!(hint, Hint::Unsolvable);
+    }
+
+    #[test]
+    fn test_area_names() {
+        assert_eq!(Area { row: 0, column: 0 }.to_string(), "top left");
+        assert_eq!(Area { row: 1, column: 2 }.to_string(), "right");
+        assert_eq!(Area { row: 1, column: 1 }.to_string(), "center");
+    }
+}
@@ -0,0 +1,34 @@
+#![allow(clippy::result_unit_err)]
+
+mod arena;
+mod catalog;
+mod difficulty;
+mod disk;
+mod feasibility;
+mod generator;
+mod hint;
+mod puzzle;
+mod solver;
+#[cfg(test)]
+#[macro_use]
+mod testing;
+mod tools;
+
+pub use catalog::{catalog, level, Level};
+pub use difficulty::{analyze, DifficultyReport};
+pub use feasibility::{check, Finding, Severity};
+pub use generator::{generate, GeneratorError, GeneratorOptions};
+pub use hint::{hint, Area, Hint, HintLevel};
+pub use puzzle::Puzzle;
+pub use solver::{solutions, solve, solve_with, Budget, Progress, SearchObserver, SearchStats, SolveError, SolveOptions, Storage, Unsolvable};
+pub use tools::*;
+
+pub(crate) fn try_into_array<I: Iterator, const N: usize>(mut it: I) -> Result<[I::Item; N], ()> {
+    // it'd be cool if we could skip allocating the vec here,
+    // but it's fine.
+    let vec: Vec<_> = it.by_ref().take(N).collect();
+    if it.next().is_some() {
+        return Err(());
+    }
+    vec.try_into().map_err(|_| ())
+}
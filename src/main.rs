@@ -1,175 +1,357 @@
-mod tools;
-
-use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{self, IsTerminal};
 use std::iter::zip;
-use crate::tools::*;
-
-pub(crate) fn try_into_array<I: Iterator, const N: usize>(mut it: I) -> Result<[I::Item; N], ()> {
-    // it'd be cool if we could skip allocating the vec here,
-    // but it's fine.
-    let vec: Vec<_> = it.by_ref().take(N).collect();
-    if it.next().is_some() {
-        return Err(());
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use inbento_solver::*;
+
+const USAGE: &str = "usage: inbento-solver [solve <puzzle file> [--max-states N] [--max-depth N] [--timeout SECS] [--disk DIR] | batch <puzzle dir> [--jobs N] [--max-states N] [--max-depth N] [--timeout SECS] | check <puzzle file> | rate <puzzle file> [--max-states N] [--max-depth N] [--timeout SECS] | hint <puzzle file> [tool|area|placement] | generate [--seed N] [--tools N] [--ingredients N]]";
+
+// Assumes s1 is uniform width, because that's our use case.
+fn inline_multiline_strs(s1: &str, s2: &str) -> String {
+    let mut out = String::new();
+    for (a, b) in zip(s1.lines(), s2.lines()) {
+        out.push_str(a);
+        out.push_str("   ");
+        out.push_str(b);
+        out.push('\n');
     }
-    vec.try_into().map_err(|_| ())
+    out
 }
 
-fn swap_remove_each<T: Clone>(list: &[T]) -> impl Iterator<Item=(T, Vec<T>)> + '_ {
-    (0..list.len()).map(|idx| {
-        let mut list = list.to_vec();
-        let elem = list.swap_remove(idx);
-        (elem, list)
-    })
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{err}");
+        process::exit(1);
+    }
 }
 
-fn solve(board: &Board, goal: &Board, tools: &[Tool]) -> Result<Vec<(Board, Tool)>, ()> {
-    // TODO: `tools` in the `frontier` might make sense as a `Rc<Vec<Tools>>`
-    let start = (board.clone(), tools.to_vec());
-    let mut paths = HashMap::new();
-    paths.insert(start.clone(), None);
-
-    let mut frontier = VecDeque::new();
-    frontier.push_back(start);
-    // let mut i = 0;
-    while let Some((board, tools)) = frontier.pop_front() {
-        // i += 1;
-        // if i % 100 == 0 {
-        //     println!("{i}");
-        // }
-        if &board == goal {
-            return Ok(rebuild_path((board, tools), paths));
+fn run() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => demo(),
+        Some("solve") => {
+            let Some(path) = args.get(1) else { return Err(USAGE.into()) };
+            let (mut options, timeout) = solve_options(&args[2..])?;
+            options.deadline = timeout.map(|timeout| Instant::now() + timeout);
+            let puzzle = read_puzzle(path)?;
+            print_solution(&puzzle.board, &puzzle.goal, &puzzle.tools, &options)
         }
-        for (tool, next_tools) in swap_remove_each(&tools) {
-            for action in tool.all_transformations() {
-                match &action {
-                    Tool::Push(fig) => {
-                        let next_board = board.apply_push(fig);
-                        let state = (next_board, next_tools.clone());
-                        let prev = (board.clone(), tools.clone(), action);
-                        paths.insert(state.clone(), Some(prev));
-                        frontier.push_back(state);
-                    }
-                    Tool::Lift(fig) => {
-                        let (next_board, piece) = board.apply_lift(fig);
-                        let mut next_tools = next_tools.clone();
-                        next_tools.push(Tool::Piece(piece));
-                        let state = (next_board, next_tools);
-                        let prev = (board.clone(), tools.clone(), action);
-                        paths.insert(state.clone(), Some(prev));
-                        frontier.push_back(state);
-                    }
-                    Tool::Piece(fig) => {
-                        let next_board = board.apply_piece(fig);
-                        let state = (next_board, next_tools.clone());
-                        let prev = (board.clone(), tools.clone(), action);
-                        paths.insert(state.clone(), Some(prev));
-                        frontier.push_back(state);
-                    },
-                    Tool::Copy(fig) => {
-                        let next_board = board.apply_copy(fig);
-                        let state = (next_board, next_tools.clone());
-                        let prev = (board.clone(), tools.clone(), action);
-                        paths.insert(state.clone(), Some(prev));
-                        frontier.push_back(state);
-                    }
-                    Tool::Swap(fig) => {
-                        let next_board = board.apply_swap(fig);
-                        let state = (next_board, next_tools.clone());
-                        let prev = (board.clone(), tools.clone(), action);
-                        paths.insert(state.clone(), Some(prev));
-                        frontier.push_back(state);
-                    }
-                }
+        Some("batch") => batch_cmd(&args[1..]),
+        Some("check") => {
+            let [path] = &args[1..] else { return Err(USAGE.into()) };
+            let puzzle = read_puzzle(path)?;
+            let findings = check(&puzzle.board, &puzzle.goal, &puzzle.tools, &Rules::default());
+            for finding in &findings {
+                println!("{}: {finding}", finding.severity());
+            }
+            if findings.iter().any(|finding| finding.severity() == Severity::Fatal) {
+                return Err("puzzle can't be solved".into());
+            }
+            if findings.is_empty() {
+                println!("no problems found");
+            }
+            Ok(())
+        }
+        Some("rate") => {
+            let Some(path) = args.get(1) else { return Err(USAGE.into()) };
+            let (mut options, timeout) = solve_options(&args[2..])?;
+            if options.storage != Storage::Memory {
+                return Err("rate keeps its states in memory, so can't use --disk".into());
             }
+            options.deadline = timeout.map(|timeout| Instant::now() + timeout);
+            let puzzle = read_puzzle(path)?;
+            let report = analyze(&puzzle.board, &puzzle.goal, &puzzle.tools, &options)
+                .map_err(|err| err.to_string())?;
+            print!("{report}");
+            Ok(())
+        }
+        Some("hint") => {
+            let (path, level) = match &args[1..] {
+                [path] => (path, HintLevel::Tool),
+                [path, level] => (path, match level.as_str() {
+                    "tool" => HintLevel::Tool,
+                    "area" => HintLevel::Area,
+                    "placement" => HintLevel::Placement,
+                    _ => return Err(USAGE.into()),
+                }),
+                _ => return Err(USAGE.into()),
+            };
+            let puzzle = read_puzzle(path)?;
+            println!("{}", hint(&puzzle.board, &puzzle.goal, &puzzle.tools, &Rules::default(), level));
+            Ok(())
         }
+        Some("generate") => generate_cmd(&args[1..]),
+        Some(_) => Err(USAGE.into()),
     }
-    Err(())
 }
-type GameState = (Board, Vec<Tool>);
-fn rebuild_path(goal: GameState, mut paths: HashMap<GameState, Option<(Board, Vec<Tool>, Tool)>>) -> Vec<(Board, Tool)> {
-    let mut path = Vec::new();
-    let mut state = goal;
-    while let Some((board, tools, tool)) = paths.remove(&state).flatten() {
-        path.push((board.clone(), tool));
-        state = (board, tools);
+
+fn read_puzzle(path: impl AsRef<Path>) -> Result<Puzzle, String> {
+    let path = path.as_ref();
+    let string = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    // puzzles laid out side by side start with a row of labels, rather
+    // than a `key: value`.
+    let side_by_side = string.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .is_some_and(|line| !line.contains(':'));
+    let puzzle = if side_by_side { Puzzle::from_side_by_side(&string, &Registry::default()) } else { Puzzle::from_str(&string) };
+    puzzle.map_err(|err| format!("{}: {err}", path.display()))
+}
+
+/// Shows how the search is going on a single line of stderr,
+/// cleared once the search is over.
+struct ProgressBar;
+
+impl SearchObserver for ProgressBar {
+    fn progress(&self, progress: &Progress) {
+        eprint!(
+            "\r\x1b[Kdepth {}  frontier {}  visited {}  ~{:.1} MiB  {:.0} states/s",
+            progress.depth,
+            progress.frontier,
+            progress.visited,
+            progress.memory_estimate as f64 / (1024.0 * 1024.0),
+            progress.states_per_sec,
+        );
+    }
+
+    fn finished(&self, _stats: &SearchStats) {
+        eprint!("\r\x1b[K");
     }
-    path.reverse();
-    path
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum Tool {
-    Push(Push),
-    Lift(Shape),
-    Piece(Piece),
-    Copy(CopyPaste),
-    Swap(Swap),
+/// Returns the options, along with the timeout, which each search's
+/// deadline is counted from.
+fn solve_options(args: &[String]) -> Result<(SolveOptions, Option<Duration>), String> {
+    let mut options = SolveOptions::default();
+    if io::stderr().is_terminal() {
+        options.observer = Some(Arc::new(ProgressBar));
+    }
+    let mut timeout = None;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(USAGE)?;
+        let parse_error = || format!("could not parse {flag} {value}");
+        match flag.as_str() {
+            "--max-states" => options.max_states = Some(value.parse().map_err(|_| parse_error())?),
+            "--max-depth" => options.max_depth = Some(value.parse().map_err(|_| parse_error())?),
+            "--timeout" => {
+                let seconds = value.parse().map_err(|_| parse_error())?;
+                timeout = Some(Duration::try_from_secs_f64(seconds).map_err(|_| parse_error())?);
+            }
+            "--disk" => options.storage = Storage::Disk(value.into()),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    Ok((options, timeout))
 }
 
-impl Tool {
-    fn all_transformations(&self) -> Vec<Tool> {
-        match self {
-            Tool::Push(fig) => fig.all_transformations().into_iter().map(Tool::Push).collect(),
-            Tool::Lift(fig) => fig.all_transformations().into_iter().map(Tool::Lift).collect(),
-            Tool::Piece(fig) => fig.all_transformations().into_iter().map(Tool::Piece).collect(),
-            Tool::Copy(fig) => fig.all_transformations().into_iter().map(Tool::Copy).collect(),
-            Tool::Swap(fig) => fig.all_transformations().into_iter().map(Tool::Swap).collect(),
+fn generate_cmd(args: &[String]) -> Result<(), String> {
+    let mut options = GeneratorOptions::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(USAGE)?;
+        let parse_error = |_| format!("could not parse {flag} {value}");
+        match flag.as_str() {
+            "--seed" => options.seed = value.parse().map_err(parse_error)?,
+            "--tools" => options.tools = value.parse().map_err(parse_error)?,
+            "--ingredients" => options.ingredients = value.parse().map_err(parse_error)?,
+            _ => return Err(USAGE.into()),
         }
     }
+    let puzzle = generate(&options).map_err(|err| err.to_string())?;
+    print!("{puzzle}");
+    Ok(())
 }
 
-// Assumes s1 is uniform width, because that's our use case.
-fn inline_multiline_strs(s1: &str, s2: &str) -> String {
+fn demo() -> Result<(), String> {
+    let Level { puzzle, .. } = level("demo").expect("the demo level is in the catalog");
+    print_solution(&puzzle.board, &puzzle.goal, &puzzle.tools, &SolveOptions::default())
+}
+
+fn print_solution(board: &Board, goal: &Board, tools: &[Tool], options: &SolveOptions) -> Result<(), String> {
+    // fatal findings come back from `solve_with` itself.
+    let findings = check(board, goal, tools, &options.rules);
+    findings.iter()
+        .filter(|finding| finding.severity() == Severity::Warning)
+        .for_each(|finding| eprintln!("warning: {finding}"));
+    let options = SolveOptions { findings: Some(findings), ..options.clone() };
+    let solution = solve_with(board, goal, tools, &options)
+        .map_err(|err| err.to_string())?;
+    println!("{}", format_solution(&solution, goal));
+    Ok(())
+}
+
+/// Each step of the solution, with the board beside the tool used on
+/// it, and then the goal.
+/// Lay the tool's figure out a row to a line, eg `(..<)(..^)(...)` as
+/// three lines.
+fn figure_lines(tool: &Tool) -> String {
+    tool.kind().to_string().replace(")(", ")\n(").replace("][", "]\n[")
+}
+
+fn format_solution(solution: &[(Board, Tool)], goal: &Board) -> String {
     let mut out = String::new();
-    for (a, b) in zip(s1.lines(), s2.lines()) {
-        out.push_str(a);
-        out.push_str("   ");
-        out.push_str(b);
+    for (board, tool) in solution {
+        out.push_str(&inline_multiline_strs(&format!("{board:?}"), &format!("\n{}", figure_lines(tool))));
         out.push('\n');
     }
+    out.push_str(&format!("{goal:?}"));
     out
 }
 
-fn main() -> Result<(), &'static str> {
-    // let board = Board::from_str("
-    //     [122]
-    //     [111]
-    //     [112]
-    // ")?;
-    // let goal = Board::from_str("
-    //     [121]
-    //     [121]
-    //     [121]
-    // ")?;
-    // let tools = vec![
-    //     Tool::Push(Push::from_str("(>)")?), // 36
-    //     Tool::Push(Push::from_str("(>)")?), // 36
-    //     Tool::Push(Push::from_str("(>>)")?), // 24
-    //     Tool::Lift(Shape::from_str("(##)")?), // oof
-    // ];
-    let board = Board::from_str("
-        [131]
-        [111]
-        [113]
-    ")?;
-    let goal = Board::from_str("
-        [211]
-        [121]
-        [112]
-    ")?;
-    let tools = vec![
-        Tool::Piece(Piece::from_str("(22)(.2)")?),
-        Tool::Swap(Swap::from_str("(#.#)")?),
-        Tool::Swap(Swap::from_str("(##)")?),
-        Tool::Push(Push::from_str("(v<)")?),
-    ];
-
-    let solution = solve(&board, &goal, &tools).unwrap();
-    for (board, tool) in solution {
-        let step = inline_multiline_strs(&format!("{board:?}"), &format!("{tool:?}"));
-        println!("{step}");
+/// How solving one puzzle of a batch went.
+struct BatchRow {
+    name: String,
+    status: String,
+    moves: Option<usize>,
+    expected: Option<usize>,
+    states: Option<usize>,
+    time: Duration,
+    /// Whether the batch should fail because of this puzzle.
+    failed: bool,
+}
+
+/// Remembers how far the search got, for the batch's table.
+#[derive(Default)]
+struct StatsRecorder(Mutex<Option<SearchStats>>);
+
+impl SearchObserver for StatsRecorder {
+    fn progress(&self, _progress: &Progress) {}
+
+    fn finished(&self, stats: &SearchStats) {
+        *self.0.lock().unwrap() = Some(stats.clone());
+    }
+}
+
+/// Solve every `.txt` puzzle in the directory, writing each solution beside
+/// its puzzle, and fail if any known solution length got worse.
+fn batch_cmd(args: &[String]) -> Result<(), String> {
+    let Some(dir) = args.first() else { return Err(USAGE.into()) };
+    let mut jobs = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let mut flags = Vec::new();
+    for pair in args[1..].chunks(2) {
+        match pair {
+            [flag, value] if flag == "--jobs" => {
+                jobs = value.parse().map_err(|_| format!("could not parse {flag} {value}"))?;
+            }
+            _ => flags.extend_from_slice(pair),
+        }
+    }
+    let (mut options, timeout) = solve_options(&flags)?;
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir).map_err(|err| format!("{dir}: {err}"))?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|extension| extension == "txt"))
+        .filter(|path| path.file_name().is_some_and(|name| !name.to_string_lossy().starts_with('.')))
+        .collect();
+    paths.sort();
+
+    // the puzzles are handed out one at a time, so a slow one doesn't
+    // hold up the rest.
+    options.observer = None;
+    let next = AtomicUsize::new(0);
+    let mut rows: Vec<(usize, BatchRow)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.clamp(1, paths.len().max(1)))
+            .map(|_| scope.spawn(|| {
+                let mut rows = Vec::new();
+                loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = paths.get(idx) else { break rows };
+                    rows.push((idx, batch_one(path, &options, timeout)));
+                }
+            }))
+            .collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+    rows.sort_by_key(|(idx, _)| *idx);
+
+    let width = rows.iter().map(|(_, row)| row.name.len()).chain(["puzzle".len()]).max().unwrap_or(0);
+    let show = |value: Option<usize>| value.map_or("-".to_string(), |value| value.to_string());
+    println!("{:<width$}  {:<10}  {:>5}  {:>8}  {:>10}  {:>9}", "puzzle", "status", "moves", "expected", "states", "time");
+    for (_, row) in &rows {
+        println!(
+            "{:<width$}  {:<10}  {:>5}  {:>8}  {:>10}  {:>9}",
+            row.name,
+            row.status,
+            show(row.moves),
+            show(row.expected),
+            show(row.states),
+            format!("{:.2?}", row.time),
+        );
+    }
+    let failed = rows.iter().filter(|(_, row)| row.failed).count();
+    if failed > 0 {
+        return Err(format!("{failed} of {} puzzles failed", rows.len()));
     }
-    println!("{goal:?}");
     Ok(())
 }
+
+fn batch_one(path: &Path, options: &SolveOptions, timeout: Option<Duration>) -> BatchRow {
+    let started = Instant::now();
+    let mut row = BatchRow {
+        name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        status: String::new(),
+        moves: None,
+        expected: None,
+        states: None,
+        time: Duration::ZERO,
+        failed: false,
+    };
+    let puzzle = match read_puzzle(path) {
+        Ok(puzzle) => puzzle,
+        Err(err) => {
+            eprintln!("{err}");
+            row.time = started.elapsed();
+            row.status = "invalid".into();
+            row.failed = true;
+            return row;
+        }
+    };
+    row.expected = puzzle.moves;
+
+    let recorder = Arc::new(StatsRecorder::default());
+    let options = SolveOptions {
+        deadline: timeout.map(|timeout| Instant::now() + timeout),
+        observer: Some(recorder.clone()),
+        ..options.clone()
+    };
+    let result = solve_with(&puzzle.board, &puzzle.goal, &puzzle.tools, &options);
+    row.time = started.elapsed();
+    row.states = recorder.0.lock().unwrap().as_ref().map(|stats| stats.states_explored);
+    match result {
+        Ok(solution) => {
+            row.moves = Some(solution.len());
+            row.status = match puzzle.moves {
+                None => "solved",
+                Some(expected) if solution.len() > expected => "REGRESSED",
+                Some(expected) if solution.len() < expected => "improved",
+                Some(_) => "ok",
+            }.into();
+            row.failed = row.status == "REGRESSED";
+            let solution_path = path.with_extension("solution");
+            if let Err(err) = fs::write(&solution_path, format_solution(&solution, &puzzle.goal) + "\n") {
+                eprintln!("{}: {err}", solution_path.display());
+                row.status = "unwritten".into();
+                row.failed = true;
+            }
+        }
+        Err(err) => {
+            row.status = match err {
+                SolveError::Unsolvable(_) => "unsolvable",
+                SolveError::Timeout(..) => "timeout",
+                SolveError::Cancelled(_) => "cancelled",
+                SolveError::Io(_) | SolveError::InvalidCell(_) | SolveError::Unmapped(_) => "error",
+            }.into();
+            // a puzzle known to have a solution should still get one.
+            row.failed = puzzle.moves.is_some() || matches!(err, SolveError::Io(_) | SolveError::InvalidCell(_) | SolveError::Unmapped(_));
+        }
+    }
+    row
+}
@@ -0,0 +1,236 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::tools::{Board, ParserError, Registry, Tool};
+
+/// A level: the board to start from, the goal to reach, and the tools
+/// to reach it with.
+///
+/// Puzzles are written one `key: value` per line, with one line per tool
+/// keyed by the tool's name. Lines indented further than the entry before
+/// them continue that entry, and lines starting with `#` are comments:
+///
+/// ```text
+/// board:
+///   [131]
+///   [111]
+///   [113]
+/// goal: [211][121][112]
+/// piece: (22)(.2)
+/// swap: (#.#)
+/// moves: 2
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Puzzle {
+    pub board: Board,
+    pub goal: Board,
+    pub tools: Vec<Tool>,
+    /// The length of the shortest solution, if known.
+    pub moves: Option<usize>,
+}
+
+/// Parses a puzzle file with only the built-in kinds of tool.
+impl FromStr for Puzzle {
+    type Err = ParserError;
+
+    fn from_str(string: &str) -> Result<Self, ParserError> {
+        Puzzle::parse(string, &Registry::default())
+    }
+}
+
+impl Puzzle {
+    /// Parse a puzzle file, reading its tools by the kinds in `kinds`.
+    pub fn parse(string: &str, kinds: &Registry) -> Result<Self, ParserError> {
+        // join continuation lines onto the entry they continue.
+        let mut entries: Vec<(usize, String)> = Vec::new();
+        for line in string.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let indent = line.len() - trimmed.len();
+            match entries.last_mut() {
+                Some((entry_indent, entry)) if indent > *entry_indent => entry.push_str(trimmed),
+                _ => entries.push((indent, trimmed.to_string())),
+            }
+        }
+
+        let entries = entries.iter()
+            .map(|(_, entry)| entry.split_once(':').ok_or("expected `key: value`"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Puzzle::from_entries(entries, kinds)
+    }
+
+    /// Parse a puzzle laid out side by side, as the game shows it, with
+    /// a row of labels above the figures. Labels are the same as the keys
+    /// of a puzzle file, and each figure is written below its label,
+    /// apart from the others by at least one column of spaces:
+    ///
+    /// ```text
+    /// board  goal   piece  swap   moves
+    /// [131]  [211]  (22)   (#.#)  2
+    /// [111]  [121]  (.2)
+    /// [113]  [112]
+    /// ```
+    ///
+    /// As in puzzle files, lines starting with `#` are comments, and
+    /// tools are read by the kinds in `kinds`.
+    pub fn from_side_by_side(string: &str, kinds: &Registry) -> Result<Self, ParserError> {
+        let lines: Vec<Vec<char>> = string.lines()
+            .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|line| line.chars().collect())
+            .collect();
+        let (labels, rows) = lines.split_first().ok_or("expected a row of labels")?;
+
+        // each figure takes up the columns between columns of spaces.
+        let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+        let used = |x: usize| lines.iter().any(|line| line.get(x).is_some_and(|c| !c.is_whitespace()));
+        let mut columns = Vec::new();
+        let mut x = 0;
+        while x < width {
+            let start = x;
+            while x < width && used(x) {
+                x += 1;
+            }
+            if x > start {
+                columns.push(start..x);
+            }
+            x += 1;
+        }
+
+        let entries: Vec<(String, String)> = columns.into_iter()
+            .map(|columns| {
+                let text = |line: &Vec<char>| -> String {
+                    line.iter().skip(columns.start).take(columns.len()).collect()
+                };
+                let label = text(labels).trim().to_string();
+                if label.is_empty() || label.contains(char::is_whitespace) {
+                    return Err("expected one label above each figure");
+                }
+                Ok((label, rows.iter().map(text).collect()))
+            })
+            .collect::<Result<_, _>>()?;
+        Puzzle::from_entries(entries.iter().map(|(label, figure)| (label.as_str(), figure.as_str())), kinds)
+    }
+
+    fn from_entries<'a>(entries: impl IntoIterator<Item=(&'a str, &'a str)>, kinds: &Registry) -> Result<Self, ParserError> {
+        let mut board = None;
+        let mut goal = None;
+        let mut tools = Vec::new();
+        let mut moves = None;
+        for (key, value) in entries {
+            match key.trim() {
+                "board" => board = Some(Board::from_str(value)?),
+                "goal" => goal = Some(Board::from_str(value)?),
+                "moves" => moves = Some(value.trim().parse().map_err(|_| "could not parse moves")?),
+                name => tools.push(kinds.parse(name, value)?),
+            }
+        }
+        Ok(Puzzle {
+            board: board.ok_or("puzzle must have a board")?,
+            goal: goal.ok_or("puzzle must have a goal")?,
+            tools,
+            moves,
+        })
+    }
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "board: {}", self.board)?;
+        writeln!(f, "goal: {}", self.goal)?;
+        for tool in &self.tools {
+            writeln!(f, "{}: {}", tool.name(), tool.kind())?;
+        }
+        if let Some(moves) = self.moves {
+            writeln!(f, "moves: {moves}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Dispenser;
+    use crate::tools::{Piece, Swap};
+
+    #[test]
+    fn test_parse() {
+        let puzzle = Puzzle::from_str("
+            # the first half of the demo level
+            board:
+              [131]
+              [111]
+              [113]
+            goal: [211][121][112]
+            piece: (22)(.2)
+            swap: (#.#)
+            moves: 2
+        ").unwrap();
+        assert_eq!(puzzle.board, Board::from_str("[131][111][113]").unwrap());
+        assert_eq!(puzzle.goal, Board::from_str("[211][121][112]").unwrap());
+        assert_eq!(puzzle.tools, vec![
+            Tool::new(Piece::from_str("(22)(.2)").unwrap()),
+            Tool::new(Swap::from_str("(#.#)").unwrap()),
+        ]);
+        assert_eq!(puzzle.moves, Some(2));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let puzzle = Puzzle::from_str("
+            board: [131][111][113]
+            goal: [211][121][112]
+            piece: ~(22)(.2)
+            swap: (##)
+        ").unwrap();
+        assert_eq!(Puzzle::from_str(&puzzle.to_string()), Ok(puzzle));
+    }
+
+    #[test]
+    fn test_missing_goal() {
+        let err = Puzzle::from_str("board: [131][111][113]");
+        assert!(err.is_err(), "{err:?}");
+    }
+
+    #[test]
+    fn test_parse_other_kinds() {
+        let string = "board: [...][...][...]\ngoal: [1..][...][...]\ndispenser: (#)";
+        assert!(Puzzle::from_str(string).is_err());
+        let puzzle = Puzzle::parse(string, &Registry::default().with::<Dispenser>()).unwrap();
+        assert_eq!(puzzle.tools, [Tool::new(Dispenser::from_str("(#)").unwrap())]);
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let puzzle = Puzzle::from_side_by_side("
+            # the demo level
+            board  goal   piece  swap   swap  push  moves
+            [131]  [211]  (22)   (#.#)  (##)  (v<)  4
+            [111]  [121]  (.2)
+            [113]  [112]
+        ", &Registry::default()).unwrap();
+        assert_eq!(Some(puzzle), crate::catalog::level("demo").map(|level| level.puzzle));
+    }
+
+    #[test]
+    fn test_side_by_side_labels() {
+        // two figures under one label.
+        let err = Puzzle::from_side_by_side("
+            board         goal
+            [131]  [211]
+            [111]  [121]
+            [113]  [112]
+        ", &Registry::default());
+        assert!(err.is_err(), "{err:?}");
+        // figures run together.
+        let err = Puzzle::from_side_by_side("
+            board goal
+            [131][211]
+            [111][121]
+            [113][112]
+        ", &Registry::default());
+        assert!(err.is_err(), "{err:?}");
+        assert!(Puzzle::from_side_by_side("", &Registry::default()).is_err());
+    }
+}
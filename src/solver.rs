@@ -0,0 +1,832 @@
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::zip;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::arena::{pack_board, Arena, Packer};
+use crate::disk;
+use crate::feasibility::{check, Finding, Severity};
+use crate::tools::{canonical_board, symmetries, Board, CellMask, Rules, Symmetry, Tool};
+
+fn swap_remove_each<T: Clone>(list: &[T]) -> impl Iterator<Item=(T, Vec<T>)> + '_ {
+    (0..list.len()).map(|idx| {
+        let mut list = list.to_vec();
+        let elem = list.swap_remove(idx);
+        (elem, list)
+    })
+}
+
+/// Why a puzzle couldn't be solved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unsolvable {
+    /// How many states were searched before giving up. Zero if the puzzle
+    /// was rejected without searching.
+    pub states_explored: usize,
+    /// What was found by checking the puzzle before searching. If any
+    /// are fatal, the puzzle was rejected without searching.
+    pub findings: Vec<Finding>,
+    /// How many of each ingredient the goal has.
+    pub goal_counts: [u8; 10],
+    /// If no board reached had the same ingredients as the goal, the
+    /// ingredients of the board which came closest.
+    pub closest_counts: Option<[u8; 10]>,
+}
+
+impl fmt::Display for Unsolvable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.states_explored == 0 {
+            write!(f, "no solution (rejected without searching)")?;
+        } else {
+            write!(f, "no solution after exploring {} states", self.states_explored)?;
+        }
+        for finding in &self.findings {
+            write!(f, "\n  {finding}")?;
+        }
+        if let Some(closest) = self.closest_counts {
+            write!(f, "\n  no board reached has the goal's ingredients")?;
+            write!(f, "\n    goal:    {}", counts_to_string(&self.goal_counts))?;
+            write!(f, "\n    closest: {}", counts_to_string(&closest))?;
+        }
+        Ok(())
+    }
+}
+
+/// Limits on how long `solve_with` may search for.
+#[derive(Clone, Debug, Default)]
+pub struct SolveOptions {
+    pub rules: Rules,
+    /// Give up after exploring this many states.
+    pub max_states: Option<usize>,
+    /// Only look for solutions up to this many moves long.
+    pub max_depth: Option<usize>,
+    /// Give up once this time has passed.
+    pub deadline: Option<Instant>,
+    /// Give up as soon as this is set, eg from another thread.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Told how the search is going every so often.
+    pub observer: Option<Arc<dyn SearchObserver>>,
+    /// Where the states found are kept.
+    pub storage: Storage,
+    /// What `check` found about the puzzle, if it's already been checked,
+    /// so that it isn't checked again before searching.
+    pub findings: Option<Vec<Finding>>,
+}
+
+/// Where a search keeps the states it finds.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Storage {
+    /// In memory, which is fastest, as long as they fit.
+    #[default]
+    Memory,
+    /// In sorted files in a temporary directory made inside this one,
+    /// for searches too big to fit in memory. Slower, and without
+    /// skipping reordered commuting moves, but only a bounded number of
+    /// states is kept in memory at once.
+    Disk(PathBuf),
+}
+
+/// How a search is going, as reported to a `SearchObserver`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// How many moves deep the search has reached.
+    pub depth: usize,
+    /// States found, but not yet explored.
+    pub frontier: usize,
+    /// States found so far, explored or not.
+    pub visited: usize,
+    pub states_explored: usize,
+    /// A rough estimate, in bytes, of the memory taken by the states found.
+    pub memory_estimate: usize,
+    /// Zero until any states have been explored.
+    pub states_per_sec: f64,
+    pub elapsed: Duration,
+}
+
+/// Something which wants to know how a search is going, eg to show a
+/// progress bar or to log statistics.
+pub trait SearchObserver: Send + Sync {
+    /// Called at the start of each layer of the search, and every so
+    /// often within one.
+    fn progress(&self, progress: &Progress);
+
+    /// Called once the search is over, however it ended, with how far
+    /// it got.
+    fn finished(&self, _stats: &SearchStats) {}
+}
+
+impl fmt::Debug for dyn SearchObserver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SearchObserver")
+    }
+}
+
+/// How many states are explored between reports to the observer.
+pub(crate) const REPORT_EVERY: usize = 1000;
+
+/// Which of the `SolveOptions` limits ran out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Budget {
+    States,
+    Depth,
+    Time,
+}
+
+/// How far a search got before it ended.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub states_explored: usize,
+    /// How many moves deep the search had reached.
+    pub depth: usize,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} states explored, {} moves deep, in {:.1?}", self.states_explored, self.depth, self.elapsed)
+    }
+}
+
+/// Why `solve_with` didn't return a solution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    /// The whole search was made, and there's no solution.
+    Unsolvable(Unsolvable),
+    /// The search ran out of budget before finding a solution, or
+    /// showing there's none.
+    Timeout(Budget, SearchStats),
+    /// The search was cancelled.
+    Cancelled(SearchStats),
+    /// The search's files couldn't be read or written.
+    Io(String),
+    /// The board or the goal has a cell which isn't an ingredient, 0 to 9.
+    InvalidCell(u8),
+    /// A move of the solution was made on a board turned by a symmetry,
+    /// and the tool couldn't be turned back. Only a kind of tool which
+    /// maps under symmetries while its placements don't can cause this.
+    Unmapped(Tool),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Unsolvable(unsolvable) => write!(f, "{unsolvable}"),
+            SolveError::Timeout(budget, stats) => {
+                let budget = match budget {
+                    Budget::States => "states",
+                    Budget::Depth => "moves",
+                    Budget::Time => "time",
+                };
+                write!(f, "gave up after running out of {budget} ({stats})")
+            }
+            SolveError::Cancelled(stats) => write!(f, "cancelled ({stats})"),
+            SolveError::Io(err) => write!(f, "search files failed: {err}"),
+            SolveError::InvalidCell(cell) => write!(f, "{cell} isn't an ingredient, which go from 0 to 9"),
+            SolveError::Unmapped(tool) => write!(f, "could not turn {tool} back to replay the solution"),
+        }
+    }
+}
+
+fn counts_to_string(counts: &[u8; 10]) -> String {
+    let counts: Vec<_> = counts.iter().enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(ingredient, count)| format!("{count}×{ingredient}"))
+        .collect();
+    if counts.is_empty() { "nothing".to_string() } else { counts.join(", ") }
+}
+
+pub fn solve(board: &Board, goal: &Board, tools: &[Tool], rules: &Rules) -> Result<Vec<(Board, Tool)>, SolveError> {
+    let options = SolveOptions { rules: rules.clone(), ..Default::default() };
+    solve_with(board, goal, tools, &options)
+}
+
+/// Like `solve`, but stopping early if any of the limits in `options`
+/// run out.
+pub fn solve_with(board: &Board, goal: &Board, tools: &[Tool], options: &SolveOptions) -> Result<Vec<(Board, Tool)>, SolveError> {
+    let started = Instant::now();
+    let mut stats = SearchStats::default();
+    let result = search(board, goal, tools, options, started, &mut stats);
+    if let Some(observer) = &options.observer {
+        stats.elapsed = started.elapsed();
+        observer.finished(&stats);
+    }
+    result
+}
+
+/// Keeps `so_far` up to date as it goes, for the observer.
+fn search(
+    board: &Board,
+    goal: &Board,
+    tools: &[Tool],
+    options: &SolveOptions,
+    started: Instant,
+    so_far: &mut SearchStats,
+) -> Result<Vec<(Board, Tool)>, SolveError> {
+    if let Some(&cell) = board.cells().iter().chain(goal.cells()).flatten().find(|&&cell| cell > 9) {
+        return Err(SolveError::InvalidCell(cell));
+    }
+    let rules = &options.rules;
+    let goal_counts = goal.ingredient_counts();
+    let findings = options.findings.clone().unwrap_or_else(|| check(board, goal, tools, rules));
+    if findings.iter().any(|finding| finding.severity() == Severity::Fatal) {
+        return Err(SolveError::Unsolvable(Unsolvable { states_explored: 0, findings, goal_counts, closest_counts: None }));
+    }
+    if let Storage::Disk(dir) = &options.storage {
+        return disk::search(board, goal, tools, options, dir, findings, started, so_far);
+    }
+
+    // boards which are images of each other under these are just as far
+    // from the goal, so only the least of them is searched. See `Arena`.
+    let symmetries = symmetries(goal, tools);
+    let goal_bits = pack_board(goal);
+
+    let mut arena = Arena::new(board, canonical(tools.to_vec()));
+    // the search goes a layer at a time, so that every way into a state
+    // is known before any way out of it is tried. See `Step`. States are
+    // numbered in the order they're found, so each layer is a range.
+    let mut layer = 0..1;
+    let mut arrivals: Vec<Vec<Step>> = vec![Vec::new()];
+    let mut depth = 0;
+    let mut states_explored = 0;
+    let mut closest: Option<(usize, [u8; 10])> = None;
+    // each tool's placements, along with their keys and footprints, are
+    // only worked out the first time the tool is tried.
+    let mut placements: HashMap<Tool, Vec<(Tool, Step)>> = HashMap::new();
+    while !layer.is_empty() {
+        so_far.depth = depth;
+        let stats = move |states_explored| SearchStats { states_explored, depth, elapsed: started.elapsed() };
+        if options.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            if let Some(id) = layer.clone().find(|&id| Packer::board_bits(arena.key(id)) == goal_bits) {
+                return arena.path(id, rules);
+            }
+            // it's only a real limit if there were moves left to make.
+            if layer.clone().any(|id| !arena.packer.tools(arena.key(id)).is_empty()) {
+                return Err(SolveError::Timeout(Budget::Depth, stats(states_explored + layer.len())));
+            }
+        }
+        depth += 1;
+        let next_start = arena.len() as u32;
+        let mut next_arrivals: Vec<Vec<Step>> = Vec::new();
+        for id in layer.clone() {
+            if let Some(observer) = options.observer.as_ref().filter(|_| id == layer.start || states_explored % REPORT_EVERY == 0) {
+                let elapsed = started.elapsed();
+                observer.progress(&Progress {
+                    depth: depth - 1,
+                    frontier: arena.len() - id as usize,
+                    visited: arena.len(),
+                    states_explored,
+                    memory_estimate: arena.memory_estimate(),
+                    states_per_sec: states_per_sec(states_explored, elapsed),
+                    elapsed,
+                });
+            }
+            if let Some(err) = over_budget(options, started, states_explored, depth - 1) {
+                return Err(err);
+            }
+            states_explored += 1;
+            so_far.states_explored = states_explored;
+            if Packer::board_bits(arena.key(id)) == goal_bits {
+                return arena.path(id, rules);
+            }
+            let (board, tools) = arena.packer.unpack(arena.key(id));
+            let tools = tools.to_vec();
+            track_closest(&mut closest, &board, &goal_counts);
+
+            let arrived = std::mem::take(&mut arrivals[(id - layer.start) as usize]);
+            let mut tried = HashSet::new();
+            for (idx, tool) in tools.iter().enumerate() {
+                if !tried.insert(tool) { continue }
+                // taking a tool out leaves the rest in order.
+                let mut rest = tools.clone();
+                rest.remove(idx);
+                let rest = arena.packer.toolset(rest);
+                let placed = placements.entry(tool.clone()).or_insert_with(|| {
+                    tool.transformations().into_iter()
+                        .map(|action| {
+                            let step = Step::new(&action, rules);
+                            (action, step)
+                        })
+                        .collect()
+                });
+                for (action, step) in placed.iter() {
+                    let mut step = *step;
+                    if !arrived.is_empty() && arrived.iter().all(|prev| step.comes_before(prev)) {
+                        continue;
+                    }
+                    let (next_board, produced) = action.apply(&board, rules);
+                    let (next_board, symmetry) = canonical_board(&next_board, &symmetries);
+                    // a move which produces tools doesn't commute with the
+                    // moves using them, and a move which was turned around
+                    // isn't the move which was made.
+                    if !produced.is_empty() || symmetry != Symmetry::IDENTITY {
+                        step.footprint = CellMask::FULL;
+                    }
+                    let next_tools = if produced.is_empty() {
+                        rest
+                    } else {
+                        let mut next_tools = tools.clone();
+                        next_tools.remove(idx);
+                        next_tools.extend(produced);
+                        arena.packer.toolset(canonical(next_tools))
+                    };
+                    let key = arena.packer.pack(&next_board, next_tools);
+                    match arena.insert(key, id, action, symmetry) {
+                        (_, true) => next_arrivals.push(vec![step]),
+                        // only ways in from the layer before count.
+                        (next, false) if next >= next_start => next_arrivals[(next - next_start) as usize].push(step),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        layer = next_start..arena.len() as u32;
+        arrivals = next_arrivals;
+    }
+    Err(unsolvable(states_explored, findings, goal_counts, closest))
+}
+
+/// Returns why the search should stop before exploring another state,
+/// if it should.
+pub(crate) fn over_budget(options: &SolveOptions, started: Instant, states_explored: usize, depth: usize) -> Option<SolveError> {
+    let stats = || SearchStats { states_explored, depth, elapsed: started.elapsed() };
+    if options.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+        return Some(SolveError::Cancelled(stats()));
+    }
+    if options.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        return Some(SolveError::Timeout(Budget::Time, stats()));
+    }
+    if options.max_states.is_some_and(|max_states| states_explored >= max_states) {
+        return Some(SolveError::Timeout(Budget::States, stats()));
+    }
+    None
+}
+
+/// How fast states are being explored, or 0 before there's anything to
+/// measure.
+pub(crate) fn states_per_sec(states_explored: usize, elapsed: Duration) -> f64 {
+    if states_explored == 0 || elapsed.is_zero() {
+        return 0.0;
+    }
+    states_explored as f64 / elapsed.as_secs_f64()
+}
+
+/// Remember the board's ingredients if they're the closest yet to the goal's.
+pub(crate) fn track_closest(closest: &mut Option<(usize, [u8; 10])>, board: &Board, goal_counts: &[u8; 10]) {
+    let counts = board.ingredient_counts();
+    let distance: usize = zip(counts, goal_counts).map(|(a, b)| a.abs_diff(*b) as usize).sum();
+    if closest.is_none_or(|(closest, _)| distance < closest) {
+        *closest = Some((distance, counts));
+    }
+}
+
+pub(crate) fn unsolvable(
+    states_explored: usize,
+    findings: Vec<Finding>,
+    goal_counts: [u8; 10],
+    closest: Option<(usize, [u8; 10])>,
+) -> SolveError {
+    SolveError::Unsolvable(Unsolvable {
+        states_explored,
+        findings,
+        goal_counts,
+        closest_counts: closest.filter(|(distance, _)| *distance > 0).map(|(_, counts)| counts),
+    })
+}
+
+/// A move, as far as partial-order reduction cares: its place in an
+/// arbitrary but fixed order of every move, and the cells it touches.
+///
+/// Moves with disjoint footprints commute, so there's no need to try
+/// both orders. From each state, the search skips any move which comes
+/// before, and commutes with, *every* move which led into the state on
+/// a shortest path. No shortest path is lost: the lexicographically
+/// least shortest path to any state never takes a move right after one
+/// it commutes with and comes before, so each of its moves is tried.
+#[derive(Clone, Copy, Debug)]
+struct Step {
+    key: u64,
+    footprint: CellMask,
+}
+
+impl Step {
+    fn new(action: &Tool, rules: &Rules) -> Self {
+        Step { key: hash(action), footprint: action.footprint(rules) }
+    }
+
+    // moves whose keys collide are never pruned, which is safe.
+    fn comes_before(&self, prev: &Step) -> bool {
+        self.key < prev.key && self.footprint.is_disjoint(prev.footprint)
+    }
+}
+
+pub(crate) type GameState = (Board, Vec<Tool>);
+/// Put the tools in a consistent order, so that states holding the same
+/// tools in a different order are recognized as the same state.
+pub(crate) fn canonical(mut tools: Vec<Tool>) -> Vec<Tool> {
+    tools.sort_by_cached_key(hash);
+    tools
+}
+
+fn hash(tool: &Tool) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tool.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns each move which may be made from the state, along with the
+/// state it leads to. Tools in the resulting states are kept in a
+/// consistent order, and identical tools are only tried once.
+pub(crate) fn successors(state: &GameState, rules: &Rules) -> Vec<(Tool, GameState)> {
+    let (board, tools) = state;
+    let mut tried = HashSet::new();
+    let mut out = Vec::new();
+    for (tool, next_tools) in swap_remove_each(tools) {
+        if !tried.insert(tool.clone()) { continue }
+        for action in tool.transformations() {
+            let (next_board, produced) = action.apply(board, rules);
+            let mut next_tools = next_tools.clone();
+            next_tools.extend(produced);
+            out.push((action, (next_board, canonical(next_tools))));
+        }
+    }
+    out
+}
+
+/// The placements of a solution, in an order which doesn't depend on
+/// the order they were made in.
+pub(crate) fn placements<'a>(path: impl Iterator<Item=&'a Tool>) -> Vec<String> {
+    let mut placements: Vec<_> = path.map(Tool::to_string).collect();
+    placements.sort();
+    placements
+}
+
+/// The depth of each state, and every way it was reached from the layer
+/// before it.
+type Parents = HashMap<GameState, (usize, Vec<(GameState, Tool)>)>;
+
+/// Returns up to `limit` of the distinct shortest solutions to the puzzle.
+/// Solutions are only distinct if they place different tools, or place
+/// them differently: the same placements in a different order count as
+/// the same solution.
+pub fn solutions(board: &Board, goal: &Board, tools: &[Tool], rules: &Rules, limit: usize) -> Vec<Vec<(Board, Tool)>> {
+    let mut parents = Parents::new();
+    let start = (board.clone(), canonical(tools.to_vec()));
+    parents.insert(start.clone(), (0, Vec::new()));
+
+    let mut layer = vec![start];
+    let mut depth = 0;
+    while !layer.is_empty() {
+        let goals: Vec<_> = layer.iter().filter(|(board, _)| board == goal).collect();
+        if !goals.is_empty() {
+            let mut seen = HashSet::new();
+            let mut walked = HashSet::new();
+            let mut found = Vec::new();
+            for state in goals {
+                collect_paths(state, &parents, &mut Vec::new(), &mut walked, &mut |path| {
+                    if seen.insert(placements(path.iter().map(|(_, tool)| tool))) {
+                        found.push(path.to_vec());
+                    }
+                    found.len() < limit
+                });
+                if found.len() >= limit { break }
+            }
+            return found;
+        }
+
+        depth += 1;
+        let mut next_layer = Vec::new();
+        for state in &layer {
+            for (action, next) in successors(state, rules) {
+                match parents.entry(next) {
+                    Entry::Occupied(mut entry) => {
+                        let (found_depth, edges) = entry.get_mut();
+                        if *found_depth == depth {
+                            edges.push((state.clone(), action));
+                        }
+                    }
+                    Entry::Vacant(entry) => {
+                        next_layer.push(entry.key().clone());
+                        entry.insert((depth, vec![(state.clone(), action)]));
+                    }
+                }
+            }
+        }
+        layer = next_layer;
+    }
+    Vec::new()
+}
+
+/// Walk back the paths from the start to `state`, passing each to `visit`
+/// in order, until `visit` returns false. Returns whether to keep going.
+///
+/// Paths which only differ in the order of their placements are only
+/// walked once: a state reached with the same placements still to make
+/// as before, in `walked`, leads back to the same solutions as before.
+fn collect_paths<'a>(
+    state: &'a GameState,
+    parents: &'a Parents,
+    path: &mut Vec<(Board, Tool)>,
+    walked: &mut HashSet<(&'a GameState, Vec<String>)>,
+    visit: &mut impl FnMut(&[(Board, Tool)]) -> bool,
+) -> bool {
+    if !walked.insert((state, placements(path.iter().map(|(_, tool)| tool)))) {
+        return true;
+    }
+    let (_, edges) = &parents[state];
+    if edges.is_empty() {
+        path.reverse();
+        let keep_going = visit(path);
+        path.reverse();
+        return keep_going;
+    }
+    for (parent, action) in edges {
+        path.push((parent.0.clone(), action.clone()));
+        let keep_going = collect_paths(parent, parents, path, walked, visit);
+        path.pop();
+        if !keep_going { return false }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::testing::Dispenser;
+    use crate::tools::{Piece, Push, Swap};
+
+    fn demo() -> (Board, Board, Vec<Tool>) {
+        let board = Board::from_str("[131][111][113]").unwrap();
+        let goal = Board::from_str("[211][121][112]").unwrap();
+        let tools = vec![
+            Tool::new(Piece::from_str("(22)(.2)").unwrap()),
+            Tool::new(Swap::from_str("(#.#)").unwrap()),
+            Tool::new(Swap::from_str("(##)").unwrap()),
+            Tool::new(Push::from_str("(v<)").unwrap()),
+        ];
+        (board, goal, tools)
+    }
+
+    #[test]
+    fn test_solve() {
+        let (board, goal, tools) = demo();
+        let solution = solve(&board, &goal, &tools, &Rules::default()).unwrap();
+        assert_eq!(solution.len(), 4);
+        let mut replayed = board;
+        for (board, tool) in solution {
+            assert_eq!(replayed, board);
+            replayed = tool.apply(&board, &Rules::default()).0;
+        }
+        assert_eq!(replayed, goal);
+    }
+
+    #[test]
+    fn test_commuting_moves_stay_shortest() {
+        // the pushes and pieces can go anywhere, in any order, but only
+        // some orders reach the goal in the fewest moves.
+        let board = Board::from_str("[131][111][113]").unwrap();
+        let goal = Board::from_str("[321][113][112]").unwrap();
+        let tools = vec![
+            Tool::new(Piece::from_str("(2)").unwrap()),
+            Tool::new(Piece::from_str("(2)").unwrap()),
+            Tool::new(Push::from_str("(<)").unwrap()),
+            Tool::new(Push::from_str("(^)").unwrap()),
+        ];
+        let shortest = solutions(&board, &goal, &tools, &Rules::default(), 1);
+        let solution = solve(&board, &goal, &tools, &Rules::default()).unwrap();
+        assert_eq!(Some(solution.len()), shortest.first().map(Vec::len));
+    }
+
+    #[test]
+    fn test_symmetric() {
+        // the goal and tools look the same however the board is turned,
+        // so the solution found may be for a turned board, which has to
+        // be turned back.
+        let board = Board::from_str("[221][121][112]").unwrap();
+        let goal = Board::from_str("[121][212][121]").unwrap();
+        let tools = vec![
+            Tool::new(Swap::from_str("(##)").unwrap()),
+            Tool::new(Swap::from_str("(##)").unwrap()),
+            Tool::new(Swap::from_str("(##)").unwrap()),
+            Tool::new(Swap::from_str("(#.#)").unwrap()),
+        ];
+        assert_eq!(symmetries(&goal, &tools).len(), 8);
+        let solution = solve(&board, &goal, &tools, &Rules::default()).unwrap();
+        let shortest = solutions(&board, &goal, &tools, &Rules::default(), 1);
+        assert_eq!(Some(solution.len()), shortest.first().map(Vec::len));
+        let mut replayed = board;
+        for (board, tool) in solution {
+            assert_eq!(replayed, board);
+            replayed = tool.apply(&board, &Rules::default()).0;
+        }
+        assert_eq!(replayed, goal);
+    }
+
+    #[test]
+    fn test_symmetric_produced_tools() {
+        // the dispenser looks the same however the board is turned, but
+        // the lift it hands out doesn't, so can't be turned back.
+        let board = Board::from_str("[11.][...][1.1]").unwrap();
+        let goal = Board::from_str("[1.1][...][1.1]").unwrap();
+        let tools = vec![Tool::new(Dispenser::from_str("(#)").unwrap())];
+        assert_eq!(symmetries(&goal, &tools), vec![Symmetry::IDENTITY]);
+        let solution = solve(&board, &goal, &tools, &Rules::default()).unwrap();
+        assert_eq!(solution.len(), 3);
+        let mut replayed = board;
+        for (board, tool) in solution {
+            assert_eq!(replayed, board);
+            replayed = tool.apply(&board, &Rules::default()).0;
+        }
+        assert_eq!(replayed, goal);
+    }
+
+    #[test]
+    fn test_symmetric_search_is_smaller() {
+        // no pair of cells to swap is in line and two apart.
+        let board = Board::from_str("[221][121][112]").unwrap();
+        let goal = Board::from_str("[121][212][121]").unwrap();
+        let tools = vec![
+            Tool::new(Swap::from_str("(##)").unwrap()),
+            Tool::new(Swap::from_str("(##)").unwrap()),
+            Tool::new(Swap::from_str("(#.#)").unwrap()),
+        ];
+        let SolveError::Unsolvable(err) = solve(&board, &goal, &tools, &Rules::default()).unwrap_err() else { panic!() };
+        let everything = crate::difficulty::analyze(&board, &goal, &tools, &SolveOptions::default()).unwrap();
+        assert!(err.states_explored < everything.states_explored, "{err:?}");
+    }
+
+    #[test]
+    fn test_comes_before() {
+        let rules = Rules::default();
+        let left = Step::new(&Tool::new(Piece::from_str("[2..]").unwrap()), &rules);
+        let right = Step::new(&Tool::new(Piece::from_str("[..2]").unwrap()), &rules);
+        let both = Step::new(&Tool::new(Piece::from_str("[222]").unwrap()), &rules);
+        let (first, second) = if left.key < right.key { (left, right) } else { (right, left) };
+        assert!(first.comes_before(&second));
+        assert!(!second.comes_before(&first));
+        assert!(!first.comes_before(&both) && !both.comes_before(&first));
+    }
+
+    #[test]
+    fn test_max_states() {
+        let (board, goal, tools) = demo();
+        let options = SolveOptions { max_states: Some(10), ..Default::default() };
+        let err = solve_with(&board, &goal, &tools, &options).unwrap_err();
+        assert!(matches!(err, SolveError::Timeout(Budget::States, SearchStats { states_explored: 10, .. })), "{err:?}");
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let (board, goal, tools) = demo();
+        let options = SolveOptions { max_depth: Some(3), ..Default::default() };
+        let err = solve_with(&board, &goal, &tools, &options).unwrap_err();
+        assert!(matches!(err, SolveError::Timeout(Budget::Depth, SearchStats { depth: 3, .. })), "{err:?}");
+        let options = SolveOptions { max_depth: Some(4), ..Default::default() };
+        assert_eq!(solve_with(&board, &goal, &tools, &options).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_deadline_and_cancel() {
+        let (board, goal, tools) = demo();
+        let options = SolveOptions { deadline: Some(Instant::now()), ..Default::default() };
+        let err = solve_with(&board, &goal, &tools, &options).unwrap_err();
+        assert!(matches!(err, SolveError::Timeout(Budget::Time, _)), "{err:?}");
+        let options = SolveOptions { cancel: Some(Arc::new(AtomicBool::new(true))), ..Default::default() };
+        let err = solve_with(&board, &goal, &tools, &options).unwrap_err();
+        assert!(matches!(err, SolveError::Cancelled(_)), "{err:?}");
+    }
+
+    #[test]
+    fn test_observer() {
+        #[derive(Default)]
+        struct Recorder {
+            reports: std::sync::Mutex<Vec<Progress>>,
+            finished: std::sync::Mutex<Option<SearchStats>>,
+        }
+
+        impl SearchObserver for Recorder {
+            fn progress(&self, progress: &Progress) {
+                self.reports.lock().unwrap().push(progress.clone());
+            }
+
+            fn finished(&self, stats: &SearchStats) {
+                *self.finished.lock().unwrap() = Some(stats.clone());
+            }
+        }
+
+        let (board, goal, tools) = demo();
+        let recorder = Arc::new(Recorder::default());
+        let options = SolveOptions { observer: Some(recorder.clone()), ..Default::default() };
+        solve_with(&board, &goal, &tools, &options).unwrap();
+        let reports = recorder.reports.lock().unwrap();
+        // at least once for each layer.
+        assert!(reports.len() >= 5, "{reports:?}");
+        assert!(reports.windows(2).all(|pair| pair[0].depth <= pair[1].depth && pair[0].visited <= pair[1].visited));
+        assert_eq!(reports.last().unwrap().depth, 4);
+        assert!(reports.last().unwrap().memory_estimate > 0);
+        assert!(reports.iter().all(|report| report.states_per_sec.is_finite()), "{reports:?}");
+        let finished = recorder.finished.lock().unwrap().clone().unwrap();
+        assert_eq!(finished.depth, 4);
+        assert!(finished.states_explored > reports.last().unwrap().states_explored);
+    }
+
+    #[test]
+    fn test_invalid_cell() {
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let mut goal = board.clone();
+        goal.cells_mut()[4] = Some(15);
+        let err = solve_with(&board, &goal, &[], &SolveOptions::default()).unwrap_err();
+        assert_eq!(err, SolveError::InvalidCell(15));
+        // errors other than there being no solution come back from `solve` too.
+        assert_eq!(solve(&board, &goal, &[], &Rules::default()), Err(err));
+    }
+
+    #[test]
+    fn test_untouchable() {
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[211][111][111]").unwrap();
+        let tools = vec![Tool::new(Piece::from_str("[...][.2.][...]").unwrap())];
+        let SolveError::Unsolvable(err) = solve(&board, &goal, &tools, &Rules::default()).unwrap_err() else { panic!() };
+        assert_eq!(err.states_explored, 0);
+        assert_eq!(err.findings, vec![Finding::UntouchableCells([0].into_iter().collect())]);
+    }
+
+    #[test]
+    fn test_checked_findings() {
+        // the findings given are trusted, rather than checked again.
+        let (board, goal, tools) = demo();
+        let options = SolveOptions { findings: Some(vec![Finding::AlreadySolved]), ..Default::default() };
+        assert_eq!(solve_with(&board, &goal, &tools, &options).unwrap().len(), 4);
+        let untouchable = Finding::UntouchableCells([0].into_iter().collect());
+        let options = SolveOptions { findings: Some(vec![untouchable.clone()]), ..Default::default() };
+        let err = solve_with(&board, &goal, &tools, &options).unwrap_err();
+        assert!(matches!(err, SolveError::Unsolvable(Unsolvable { ref findings, .. }) if *findings == [untouchable]), "{err:?}");
+    }
+
+    #[test]
+    fn test_ingredient_mismatch() {
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[222][222][221]").unwrap();
+        // there are enough 2s, but the pieces can't help covering each other.
+        let tools = vec![
+            Tool::new(Piece::from_str("[22][22]").unwrap()),
+            Tool::new(Piece::from_str("[22][22]").unwrap()),
+        ];
+        let SolveError::Unsolvable(err) = solve(&board, &goal, &tools, &Rules::default()).unwrap_err() else { panic!() };
+        assert!(err.states_explored > 0);
+        assert_eq!(err.goal_counts[2], 8);
+        assert_eq!(err.closest_counts.map(|counts| counts[2]), Some(7));
+    }
+
+    #[test]
+    fn test_solutions_ignore_order() {
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[211][111][112]").unwrap();
+        let tools = vec![
+            Tool::new(Piece::from_str("(2)").unwrap()),
+            Tool::new(Piece::from_str("(2)").unwrap()),
+        ];
+        let found = solutions(&board, &goal, &tools, &Rules::default(), 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].len(), 2);
+    }
+
+    #[test]
+    fn test_solutions_commuting() {
+        // the pieces may go down in any of 9! orders, which are all the
+        // same solution.
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[222][222][222]").unwrap();
+        let tools = vec![Tool::new(Piece::from_str("(2)").unwrap()); 9];
+        assert_eq!(solutions(&board, &goal, &tools, &Rules::default(), 10).len(), 1);
+    }
+
+    #[test]
+    fn test_solutions_limit() {
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let goal = Board::from_str("[211][111][111]").unwrap();
+        // either piece will do.
+        let tools = vec![
+            Tool::new(Piece::from_str("(2)").unwrap()),
+            Tool::new(Piece::from_str("(2.)(.1)").unwrap()),
+        ];
+        assert_eq!(solutions(&board, &goal, &tools, &Rules::default(), 1).len(), 1);
+        assert_eq!(solutions(&board, &goal, &tools, &Rules::default(), 5).len(), 2);
+    }
+
+    #[test]
+    fn test_moves_tried_once() {
+        // turned around, the piece lands on the cells it covered before.
+        let board = Board::from_str("[111][111][111]").unwrap();
+        let piece = Tool::new(Piece::from_str("(.)(2)").unwrap());
+        let moves: Vec<_> = successors(&(board, vec![piece]), &Rules::default()).into_iter()
+            .map(|(action, _)| action)
+            .collect();
+        let unique: HashSet<_> = moves.iter().collect();
+        assert_eq!(unique.len(), moves.len(), "{moves:?}");
+        assert_eq!(moves.len(), 9);
+    }
+}
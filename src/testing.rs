@@ -0,0 +1,148 @@
+//! Helpers shared by the tests, including random figures and boards for
+//! checking properties over many cases.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::generator::Rng;
+use crate::tools::{Board, CellMask, Figure, FoodEffect, InbentoCell, Lift, ParserError, Rules, Symmetry, Tool, ToolKind, SIZE};
+
+/// How many random cases each property is checked against.
+pub(crate) const CASES: u64 = 300;
+
+/// Assert that two collections hold the same items, each the same
+/// number of times, in any order.
+macro_rules! assert_eq_orderless {
+    ($left:expr, $right:expr $(,)?) => {
+        assert_eq_orderless!($left, $right, "")
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {{
+        let left: Vec<_> = $left.into_iter().collect();
+        let right: Vec<_> = $right.into_iter().collect();
+        assert!(
+            $crate::testing::same_items(&left, &right),
+            "assertion `left == right` (in any order) failed: {}\n  left: {left:?}\n right: {right:?}",
+            format_args!($($arg)+),
+        );
+    }};
+}
+
+/// Whether the slices hold the same items, each the same number of
+/// times. Only needs `PartialEq`, so it's quadratic, but test data is small.
+pub(crate) fn same_items<T: PartialEq>(left: &[T], right: &[T]) -> bool {
+    let mut unmatched: Vec<&T> = right.iter().collect();
+    left.len() == right.len() && left.iter().all(|item| {
+        match unmatched.iter().position(|other| *other == item) {
+            Some(idx) => {
+                unmatched.swap_remove(idx);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Returns a random figure of up to 3×3 cells, written with `cells`,
+/// which is maybe rotatable and maybe mirrorable, and isn't empty.
+pub(crate) fn random_figure<T: InbentoCell>(rng: &mut Rng, cells: &[char]) -> Figure<T> {
+    loop {
+        let (open, close) = *rng.pick(&[('[', ']'), ('(', ')')]);
+        let mut string = String::new();
+        if rng.below(2) == 0 {
+            string.push('~');
+        }
+        let width = 1 + rng.below(SIZE);
+        for _ in 0..1 + rng.below(SIZE) {
+            string.push(open);
+            for _ in 0..width {
+                string.push(if rng.below(3) == 0 { '.' } else { *rng.pick(cells) });
+            }
+            string.push(close);
+        }
+        let figure = Figure::from_str(&string).unwrap_or_else(|err| panic!("{string}: {err}"));
+        if !figure.mask().is_empty() {
+            return figure;
+        }
+    }
+}
+
+/// Returns a random board, with some empty cells.
+pub(crate) fn random_board(rng: &mut Rng) -> Board {
+    let mut string = String::new();
+    for _ in 0..SIZE {
+        string.push('[');
+        for _ in 0..SIZE {
+            string.push(*rng.pick(&['.', '0', '1', '2', '3', '9']));
+        }
+        string.push(']');
+    }
+    Board::from_str(&string).unwrap()
+}
+
+/// A kind of tool from outside the built-in ones, which leaves the
+/// board alone, but hands out a `Lift`. It looks the same however the
+/// board is turned, though the lift it hands out can't say how it's turned.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Dispenser(Lift);
+
+impl FromStr for Dispenser {
+    type Err = ParserError;
+
+    fn from_str(string: &str) -> Result<Self, ParserError> {
+        Lift::from_str(string).map(Dispenser)
+    }
+}
+
+impl fmt::Display for Dispenser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl ToolKind for Dispenser {
+    fn parse(string: &str) -> Result<Self, ParserError> {
+        Dispenser::from_str(string)
+    }
+
+    fn name() -> &'static str { "dispenser" }
+
+    fn transformations(&self) -> Vec<Tool> {
+        vec![Tool::new(self.clone())]
+    }
+
+    fn apply(&self, board: &Board, _rules: &Rules) -> (Board, Vec<Tool>) {
+        (board.clone(), vec![Tool::new(self.0.clone())])
+    }
+
+    fn footprint(&self, _rules: &Rules) -> CellMask {
+        CellMask::EMPTY
+    }
+
+    fn reach(&self, rules: &Rules) -> CellMask {
+        Tool::new(self.0.clone()).reach(rules)
+    }
+
+    fn food_effect(&self, rules: &Rules) -> FoodEffect {
+        Tool::new(self.0.clone()).food_effect(rules)
+    }
+
+    fn map_symmetry(&self, _symmetry: Symmetry) -> Option<Tool> {
+        Some(Tool::new(self.clone()))
+    }
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orderless() {
+        assert_eq_orderless!(vec![1, 2, 2, 3], vec![2, 3, 2, 1]);
+        assert!(!same_items(&[1, 2, 2], &[1, 1, 2]));
+        assert!(!same_items(&[1, 2], &[1, 2, 2]));
+    }
+
+    #[test]
+    #[should_panic(expected = "in any order")]
+    fn test_orderless_mismatch() {
+        assert_eq_orderless!(vec![1, 2, 2], vec![2, 1, 1]);
+    }
+}
@@ -1,5 +1,5 @@
 use std::iter::zip;
-use super::{Figure, Push, Piece, Shape, CopyPaste, Swap, AREA, SIZE};
+use super::{CellMask, Direction, Figure, Push, Piece, Shape, CopyPaste, Cycle, Rotate, Slide, Swap, AREA, SIZE};
 
 pub type Board = Figure<u8>; // ehh
 
@@ -16,30 +16,115 @@ impl Board {
         let mut lifted = Piece {
             layout: Default::default(),
             rotatable: false,
+            mirrorable: false,
             bounding_width: SIZE,
             bounding_height: SIZE,
         };
         for src in 0..AREA {
             let Some(dir) = push.layout[src] else { continue };
             if out.layout[src].is_none() { continue };
-            let Some(dest) = src.checked_add_signed(dir as isize) else { continue };
-            // edge check
-            let sx = src % SIZE;
-            let sy = src / SIZE;
-            let dx = dest % SIZE;
-            let dy = dest / SIZE;
-            if (sx != dx && sy != dy) || dest >= AREA { continue }
+            let Some(dest) = step(src, dir) else { continue };
             lifted.layout[dest] = out.layout[src].take();
         }
         out.apply_piece_mut(&lifted);
         out
     }
 
+    pub fn apply_push_chain(&self, push: &Push) -> Self {
+        // each pushed cell shoves the line of food ahead of it along
+        // until the line meets an empty cell. If it meets the edge
+        // instead, the line stays put.
+        struct Chain {
+            dir: Direction,
+            cells: Vec<usize>,
+            target: usize,
+        }
+
+        let mut chains = Vec::new();
+        for src in 0..AREA {
+            let Some(dir) = push.layout[src] else { continue };
+            if self.layout[src].is_none() { continue };
+            let mut cells = vec![src];
+            let mut idx = src;
+            let target = loop {
+                match step(idx, dir) {
+                    Some(next) if self.layout[next].is_some() => {
+                        cells.push(next);
+                        idx = next;
+                    }
+                    next => break next,
+                }
+            };
+            let Some(target) = target else { continue };
+            chains.push(Chain { dir, cells, target });
+        }
+
+        // lines shoved in different directions which meet, either at
+        // some food or at the cell they're shoved into, jam each other.
+        let is_jammed = |chain: &Chain| chains.iter().any(|other| {
+            other.dir != chain.dir && (
+                other.target == chain.target
+                || other.cells.iter().any(|cell| chain.cells.contains(cell))
+            )
+        });
+
+        // as with `apply_push`, move all the cells simultaneously.
+        let mut out = self.clone();
+        let mut lifted = Piece {
+            layout: Default::default(),
+            rotatable: false,
+            mirrorable: false,
+            bounding_width: SIZE,
+            bounding_height: SIZE,
+        };
+        for chain in chains.iter().filter(|chain| !is_jammed(chain)) {
+            for &src in &chain.cells {
+                let dest = step(src, chain.dir).unwrap();
+                lifted.layout[dest] = self.layout[src];
+                out.layout[src] = None;
+            }
+        }
+        out.apply_piece_mut(&lifted);
+        out
+    }
+
+    pub fn apply_slide(&self, slide: &Slide) -> Self {
+        // all the sliding food moves a step at a time, simultaneously,
+        // until none of it can move any further. Food can only step
+        // into a cell which was empty before the step, and two foods
+        // stepping into the same cell block each other.
+        let mut out = self.clone();
+        let mut sliding: Vec<_> = (0..AREA)
+            .filter(|&src| self.layout[src].is_some())
+            .filter_map(|src| Some((src, slide.shape.layout[src]?)))
+            .collect();
+        loop {
+            let steps: Vec<_> = sliding.iter().enumerate()
+                .filter_map(|(idx, &(src, dir))| {
+                    let dest = step(src, dir)?;
+                    out.layout[dest].is_none().then_some((idx, dest))
+                })
+                .collect();
+            let steps: Vec<_> = steps.iter()
+                .filter(|(_, dest)| steps.iter().filter(|(_, other)| other == dest).count() == 1)
+                .collect();
+            if steps.is_empty() {
+                return out;
+            }
+            for &&(idx, dest) in &steps {
+                let (src, _) = &mut sliding[idx];
+                out.layout[dest] = out.layout[*src].take();
+                *src = dest;
+            }
+        }
+    }
+
     pub fn apply_lift(&self, lift: &Shape) -> (Self, Piece) {
         let mut out = self.clone();
         let mut lifted = Piece {
             layout: Default::default(),
             rotatable: lift.rotatable, // XXX: idk that this is true in-game
+            mirrorable: lift.mirrorable,
             bounding_width: SIZE,
             bounding_height: SIZE,
         };
@@ -70,48 +155,100 @@ impl Board {
     }
 
     pub fn apply_copy(&self, copy: &CopyPaste) -> Self {
-        let copied_idx = copy.copy_idx();
-        let copied_cell = self.layout[copied_idx];
         let mut out = self.clone();
-        for (src, dest) in zip(copy.shape.layout, &mut out.layout) {
-            if src.is_some() {
-                *dest = copied_cell;
-            }
+        for (src, dest) in copy.pairs() {
+            out.layout[dest] = self.layout[src];
         }
         out
     }
 
     pub fn apply_swap(&self, swap: &Swap) -> Self {
         let mut out = self.clone();
-        let [cell1, cell2] = swap.swap_idxs();
-        out.layout[cell1] = self.layout[cell2];
-        out.layout[cell2] = self.layout[cell1];
+        for [a, b] in swap.pairs() {
+            out.layout.swap(a, b);
+        }
         out
     }
+
+    pub fn apply_cycle(&self, cycle: &Cycle) -> Self {
+        self.cycle_cells(&cycle.idxs())
+    }
+
+    /// Move the food in each of the cells to the next cell in the list,
+    /// with the last cell's food moving round to the first.
+    fn cycle_cells(&self, idxs: &[usize]) -> Self {
+        let mut out = self.clone();
+        for (&src, &dest) in zip(idxs, idxs.iter().cycle().skip(1)) {
+            out.layout[dest] = self.layout[src];
+        }
+        out
+    }
+
+    pub fn apply_rotate(&self, rotate: &Rotate) -> Self {
+        let mut out = self.clone();
+        for (src, dest) in rotate.turns() {
+            out.layout[dest] = self.layout[src];
+        }
+        out
+    }
+}
+
+// ===
+// queries
+// ===
+impl Board {
+    /// Returns how many of each ingredient are on the board,
+    /// indexed by ingredient.
+    pub fn ingredient_counts(&self) -> [u8; 10] {
+        let mut counts = [0; 10];
+        for &ingredient in self.layout.iter().flatten() {
+            counts[ingredient as usize] += 1;
+        }
+        counts
+    }
 }
 
-fn min_x<T>(layout: &[Option<T>; AREA]) -> usize {
+/// Returns the index one cell over from `src` in the given direction,
+/// or `None` if that would leave the 3×3 area.
+pub(super) fn step(src: usize, dir: Direction) -> Option<usize> {
+    let dest = src.checked_add_signed(dir as isize)?;
+    // edge check
+    let sx = src % SIZE;
+    let sy = src / SIZE;
+    let dx = dest % SIZE;
+    let dy = dest / SIZE;
+    if (sx != dx && sy != dy) || dest >= AREA { return None }
+    Some(dest)
+}
+
+/// Returns every cell from `src` onward in the given direction, up to
+/// the edge of the 3×3 area.
+pub(super) fn lane(src: usize, dir: Direction) -> CellMask {
+    std::iter::successors(Some(src), |&idx| step(idx, dir)).collect()
+}
+
+pub(super) fn min_x<T>(layout: &[Option<T>; AREA]) -> usize {
     layout.iter().enumerate()
-        .flat_map(|(idx, elem)| elem.is_some().then_some(idx % 3 + 1))
+        .flat_map(|(idx, elem)| elem.is_some().then_some(idx % 3))
         .min()
         .unwrap_or(0)
 }
 
-fn min_y<T>(layout: &[Option<T>; AREA]) -> usize {
+pub(super) fn min_y<T>(layout: &[Option<T>; AREA]) -> usize {
     layout.iter().enumerate()
-        .flat_map(|(idx, elem)| elem.is_some().then_some(idx / 3 + 1))
+        .flat_map(|(idx, elem)| elem.is_some().then_some(idx / 3))
         .min()
         .unwrap_or(0)
 }
 
-fn max_x<T>(layout: &[Option<T>; AREA]) -> usize {
+pub(super) fn max_x<T>(layout: &[Option<T>; AREA]) -> usize {
     layout.iter().enumerate()
         .flat_map(|(idx, elem)| elem.is_some().then_some(idx % 3 + 1))
         .max()
         .unwrap_or(0)
 }
 
-fn max_y<T>(layout: &[Option<T>; AREA]) -> usize {
+pub(super) fn max_y<T>(layout: &[Option<T>; AREA]) -> usize {
     layout.iter().enumerate()
         .flat_map(|(idx, elem)| elem.is_some().then_some(idx / 3 + 1))
         .max()
@@ -120,7 +257,24 @@ fn max_y<T>(layout: &[Option<T>; AREA]) -> usize {
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
     use super::*;
+    use crate::generator::Rng;
+    use crate::testing::{random_board, random_figure, CASES};
+
+    /// Returns a random board, and a random push placed on it.
+    fn random_push(seed: u64) -> (Board, Push) {
+        let mut rng = Rng::new(seed);
+        let board = random_board(&mut rng);
+        let push: Push = random_figure(&mut rng, &['^', '>', 'v', '<']);
+        let placements = push.all_transformations();
+        (board, rng.pick(&placements).clone())
+    }
+
+    /// Whether there's no more of any ingredient on `after` than `before`.
+    fn no_more_food(before: &Board, after: &Board) -> bool {
+        zip(before.ingredient_counts(), after.ingredient_counts()).all(|(before, after)| after <= before)
+    }
 
     #[test]
     fn test_push() {
@@ -146,4 +300,203 @@ mod tests {
         let actual = board.apply_push(&push);
         assert_eq!(actual, board);
     }
+
+    #[test]
+    fn test_push_chain() {
+        let board = Board::from_str("[12.][4.6][789]").unwrap();
+        let push = Push::from_str("(>..)(...)(...)").unwrap();
+        let expected = Board::from_str("[.12][4.6][789]").unwrap();
+        let actual = board.apply_push_chain(&push);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_push_chain_simultaneous() {
+        let board = Board::from_str("[12.][4.6][789]").unwrap();
+        let push = Push::from_str("(>>.)(...)(^..)").unwrap();
+        // the `^` line would be shoved off the top, so only the `>` line moves.
+        let expected = Board::from_str("[.12][4.6][789]").unwrap();
+        let actual = board.apply_push_chain(&push);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_push_chain_blocked() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        let push = Push::from_str("(...)(..v)(..>)").unwrap();
+        let actual = board.apply_push_chain(&push);
+        assert_eq!(actual, board);
+    }
+
+    #[test]
+    fn test_push_chain_jammed() {
+        let board = Board::from_str("[1.2][456][789]").unwrap();
+        let push = Push::from_str("(>.<)(...)(...)").unwrap();
+        let actual = board.apply_push_chain(&push);
+        assert_eq!(actual, board);
+    }
+
+    #[test]
+    fn test_push_chain_no_wrap_right() {
+        let board = Board::from_str("[123][45.][789]").unwrap();
+        let push = Push::from_str("(..>)(...)(...)").unwrap();
+        let actual = board.apply_push_chain(&push);
+        assert_eq!(actual, board);
+    }
+
+    #[test]
+    fn test_push_chain_no_wrap_down() {
+        let board = Board::from_str("[123][456][.89]").unwrap();
+        let push = Push::from_str("(...)(...)(.v.)").unwrap();
+        let actual = board.apply_push_chain(&push);
+        assert_eq!(actual, board);
+    }
+
+    #[test]
+    fn test_copy() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        let copy = CopyPaste::from_str("[...][.CV][.V.]").unwrap();
+        let expected = Board::from_str("[123][455][759]").unwrap();
+        assert_eq!(board.apply_copy(&copy), expected);
+    }
+
+    #[test]
+    fn test_copy_region() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        let copy = CopyPaste::from_str("[CV.][CV.][...]").unwrap();
+        let expected = Board::from_str("[113][446][789]").unwrap();
+        assert_eq!(board.apply_copy(&copy), expected);
+    }
+
+    #[test]
+    fn test_swap() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        let swap = Swap::from_str("[#..][...][..#]").unwrap();
+        let expected = Board::from_str("[923][456][781]").unwrap();
+        assert_eq!(board.apply_swap(&swap), expected);
+    }
+
+    #[test]
+    fn test_swap_pairs() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        let swap = Swap::from_str("[12.][12.][...]").unwrap();
+        let expected = Board::from_str("[453][126][789]").unwrap();
+        assert_eq!(board.apply_swap(&swap), expected);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        let cycle = Cycle::from_str("[12.][43.][...]").unwrap();
+        let expected = Board::from_str("[413][526][789]").unwrap();
+        assert_eq!(board.apply_cycle(&cycle), expected);
+    }
+
+    #[test]
+    fn test_slide() {
+        let board = Board::from_str("[1.3][...][...]").unwrap();
+        let slide = Slide::from_str("(>..)(...)(...)").unwrap();
+        let expected = Board::from_str("[.13][...][...]").unwrap();
+        assert_eq!(board.apply_slide(&slide), expected);
+    }
+
+    #[test]
+    fn test_slide_line() {
+        let board = Board::from_str("[12.][...][...]").unwrap();
+        let slide = Slide::from_str("(>>.)(...)(...)").unwrap();
+        let expected = Board::from_str("[.12][...][...]").unwrap();
+        assert_eq!(board.apply_slide(&slide), expected);
+    }
+
+    #[test]
+    fn test_slide_collision() {
+        let board = Board::from_str("[1..][...][..3]").unwrap();
+        let slide = Slide::from_str("(>..)(...)(..^)").unwrap();
+        let expected = Board::from_str("[.1.][..3][...]").unwrap();
+        assert_eq!(board.apply_slide(&slide), expected);
+    }
+
+    #[test]
+    fn test_lift() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        let lift = Shape::from_str("[...][.##][..#]").unwrap();
+        let (actual, piece) = board.apply_lift(&lift);
+        assert_eq!(actual, Board::from_str("[123][4..][78.]").unwrap());
+        assert_eq!(piece, Piece::from_str("[56][.9]").unwrap());
+    }
+
+    #[test]
+    fn test_rotate_clockwise() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        let rotate = Rotate::from_str("[...][.RR][.RR]").unwrap();
+        let expected = Board::from_str("[123][485][796]").unwrap();
+        assert_eq!(board.apply_rotate(&rotate), expected);
+    }
+
+    #[test]
+    fn test_rotate_counterclockwise_ring() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        let rotate = Rotate::from_str("[LLL][L.L][LLL]").unwrap();
+        let expected = Board::from_str("[369][258][147]").unwrap();
+        assert_eq!(board.apply_rotate(&rotate), expected);
+    }
+
+    #[test]
+    fn test_push_never_creates_food() {
+        for seed in 0..CASES {
+            let (board, push) = random_push(seed);
+            assert!(no_more_food(&board, &board.apply_push(&push)), "{board:?} {push:?}");
+            // shoving a line along never drops any of it.
+            assert_eq!(board.apply_push_chain(&push).ingredient_counts(), board.ingredient_counts(), "{board:?} {push:?}");
+            let slide = Slide { shape: push };
+            assert_eq!(board.apply_slide(&slide).ingredient_counts(), board.ingredient_counts(), "{board:?} {slide:?}");
+        }
+    }
+
+    #[test]
+    fn test_swap_twice() {
+        let swaps = ["(##)", "(#.#)", "(#)(.)(#)", "(#.)(.#)", "[#..][...][..#]", "(12)(21)", "(1.2)(2.1)"];
+        for seed in 0..CASES {
+            let mut rng = Rng::new(seed);
+            let board = random_board(&mut rng);
+            let swap = *rng.pick(&swaps);
+            for swap in Swap::from_str(swap).unwrap().all_transformations() {
+                assert_eq!(board.apply_swap(&swap).apply_swap(&swap), board, "{swap:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_moves_keep_food() {
+        let cycle = Cycle::from_str("(12)(43)").unwrap();
+        let rotate = Rotate::from_str("(RRR)(R.R)(RRR)").unwrap();
+        for seed in 0..CASES {
+            let mut rng = Rng::new(seed);
+            let board = random_board(&mut rng);
+            let counts = board.ingredient_counts();
+            for cycle in cycle.all_transformations() {
+                assert_eq!(board.apply_cycle(&cycle).ingredient_counts(), counts, "{cycle:?}");
+            }
+            for rotate in rotate.all_transformations() {
+                assert_eq!(board.apply_rotate(&rotate).ingredient_counts(), counts, "{rotate:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_lift_keeps_food() {
+        for seed in 0..CASES {
+            let mut rng = Rng::new(seed);
+            let board = random_board(&mut rng);
+            let shape: Shape = random_figure(&mut rng, &['#']);
+            for placed in shape.all_transformations() {
+                // whatever's taken off the board is in the lifted piece.
+                let (lifted, piece) = board.apply_lift(&placed);
+                let counts: Vec<_> = zip(lifted.ingredient_counts(), piece.ingredient_counts())
+                    .map(|(left, taken)| left + taken)
+                    .collect();
+                assert_eq!(counts, board.ingredient_counts(), "{placed:?}");
+            }
+        }
+    }
 }
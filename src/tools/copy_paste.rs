@@ -1,6 +1,8 @@
 use std::fmt;
+use std::str::FromStr;
+use std::iter::zip;
 use crate::try_into_array;
-use super::{Figure, ParserError, InbentoCell};
+use super::{Board, Figure, ParserError, Rules, InbentoCell, CellMask, FoodEffect, Symmetry, Tool, ToolKind, SIZE};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub(super) enum CopyPasteCell {
@@ -27,6 +29,12 @@ impl InbentoCell for CopyPasteCell {
     fn rotate(&self) -> Self { *self }
 }
 
+/// Copies food onto the board. With a single `C` cell, the food under
+/// it is pasted to every `V` cell. With several `C` cells, the whole
+/// region is pasted to the `V` cells, which must be the same shape:
+///   (CV)
+///   (CV)
+/// copies a column of food into the next column over.
 // we could keep a `Shape` instead, but we'd probably want to convert
 // back for the Debug anyway.
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -40,16 +48,38 @@ impl fmt::Debug for CopyPaste {
     }
 }
 
-impl CopyPaste {
-    #[allow(dead_code)]
-    pub fn from_str(string: &str) -> Result<Self, ParserError> {
+impl fmt::Display for CopyPaste {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.shape)
+    }
+}
+
+impl FromStr for CopyPaste {
+    type Err = ParserError;
+
+    fn from_str(string: &str) -> Result<Self, ParserError> {
         let shape = Figure::from_str(string)?;
-        let copy_idxs = shape.layout.iter().enumerate()
-            .filter(|(_, cell)| matches!(cell, Some(CopyPasteCell::Copy)))
-            .map(|(idx, _)| idx);
-        let [_copy_idx] = try_into_array(copy_idxs)
-            .map_err(|_| "expected exactly one copy cell")?;
-        Ok(CopyPaste { shape })
+        let copy = CopyPaste { shape };
+        let copy_idxs = copy.idxs(CopyPasteCell::Copy);
+        if copy_idxs.is_empty() {
+            return Err("expected at least one copy cell");
+        }
+        if copy_idxs.len() > 1 {
+            let paste_idxs = copy.idxs(CopyPasteCell::Paste);
+            if copy_idxs.len() != paste_idxs.len() {
+                return Err("pasted region must be the same size as the copied region");
+            }
+            let offset = |(src, dest): (usize, usize)| (
+                (dest % SIZE) as isize - (src % SIZE) as isize,
+                (dest / SIZE) as isize - (src / SIZE) as isize,
+            );
+            let mut offsets = zip(copy_idxs, paste_idxs).map(offset);
+            let first = offsets.next();
+            if offsets.any(|other| Some(other) != first) {
+                return Err("pasted region must be the same shape as the copied region");
+            }
+        }
+        Ok(copy)
     }
 }
 
@@ -60,12 +90,54 @@ impl CopyPaste {
             .collect()
     }
 
-    pub(super) fn copy_idx(&self) -> usize {
-        let copy_idxs = self.shape.layout.iter().enumerate()
-            .filter(|(_, cell)| matches!(cell, Some(CopyPasteCell::Copy)))
-            .map(|(idx, _)| idx);
-        let [copy_idx] = try_into_array(copy_idxs).unwrap();
-        copy_idx
+    fn idxs(&self, kind: CopyPasteCell) -> Vec<usize> {
+        self.shape.layout.iter().enumerate()
+            .filter(|(_, cell)| **cell == Some(kind))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Returns the (copied, pasted) index of each cell to be pasted.
+    pub(super) fn pairs(&self) -> Vec<(usize, usize)> {
+        let copy_idxs = self.idxs(CopyPasteCell::Copy);
+        let paste_idxs = self.idxs(CopyPasteCell::Paste);
+        if let Ok([copy_idx]) = try_into_array(copy_idxs.iter().copied()) {
+            return paste_idxs.into_iter().map(|paste_idx| (copy_idx, paste_idx)).collect();
+        }
+        // a translation keeps the cells in the same order,
+        // so we can pair them up as we find them.
+        zip(copy_idxs, paste_idxs).collect()
+    }
+}
+
+impl ToolKind for CopyPaste {
+    fn parse(string: &str) -> Result<Self, ParserError> {
+        CopyPaste::from_str(string)
+    }
+
+    fn name() -> &'static str { "copy" }
+
+    fn transformations(&self) -> Vec<Tool> {
+        self.all_transformations().into_iter().map(Tool::new).collect()
+    }
+
+    fn apply(&self, board: &Board, _rules: &Rules) -> (Board, Vec<Tool>) {
+        (board.apply_copy(self), Vec::new())
+    }
+
+    fn footprint(&self, _rules: &Rules) -> CellMask {
+        self.shape.mask()
+    }
+
+    fn food_effect(&self, _rules: &Rules) -> FoodEffect {
+        let copies = self.pairs().len() as u8;
+        FoodEffect { copies, removes: true, ..FoodEffect::CONSERVES }
+    }
+
+    fn produces_tools(&self) -> bool { false }
+
+    fn map_symmetry(&self, symmetry: Symmetry) -> Option<Tool> {
+        Some(Tool::new(CopyPaste { shape: self.shape.map_symmetry(symmetry) }))
     }
 }
 
@@ -84,4 +156,17 @@ mod tests {
         let err = CopyPaste::from_str("(CVC)");
         assert!(err.is_err(), "{err:?}");
     }
+
+    #[test]
+    fn test_region_shape_validation() {
+        let err = CopyPaste::from_str("(CC.)(..V)(..V)");
+        assert!(err.is_err(), "{err:?}");
+    }
+
+    #[test]
+    fn test_region() {
+        let copy = CopyPaste::from_str("(CC.)(.VV)");
+        assert!(copy.is_ok(), "{copy:?}");
+        assert_eq!(copy.unwrap().pairs(), vec![(0, 4), (1, 5)]);
+    }
 }
@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use super::{Board, Figure, ParserError, Rules, CellMask, FoodEffect, Symmetry, Tool, ToolKind};
+
+/// Moves the food under each numbered cell to the next numbered cell,
+/// with the food under the last number moving round to the first.
+/// The numbers must run from 1, without gaps:
+///   (12)
+///   (43)
+/// turns the food in a 2×2 block clockwise.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    shape: Figure<u8>,
+}
+
+impl fmt::Debug for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.shape)
+    }
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.shape)
+    }
+}
+
+impl FromStr for Cycle {
+    type Err = ParserError;
+
+    fn from_str(string: &str) -> Result<Self, ParserError> {
+        let shape: Figure<u8> = Figure::from_str(string)?;
+        let mut numbers: Vec<_> = shape.layout.iter().flatten().copied().collect();
+        numbers.sort();
+        if numbers.len() < 2 {
+            return Err("expected at least two cycle cells");
+        }
+        if !numbers.iter().copied().eq(1..=numbers.len() as u8) {
+            return Err("cycle cells must be numbered from 1 without gaps or repeats");
+        }
+        Ok(Cycle { shape })
+    }
+}
+
+impl Cycle {
+    /// Returns the *unique* transformations of this Cycle. Transformations
+    /// which move the same cells around in the same order are equivalent,
+    /// no matter which cell is numbered first.
+    pub fn all_transformations(&self) -> Vec<Self> {
+        let mut seen = HashSet::new();
+        self.shape.all_transformations().into_iter()
+            .map(|shape| Cycle { shape }.normalized())
+            .filter(|cycle| seen.insert(cycle.idxs()))
+            .collect()
+    }
+
+    /// Renumber the cycle to start from its first cell, so that cycles
+    /// which move the same cells around in the same order are equal.
+    fn normalized(&self) -> Self {
+        let mut out = self.clone();
+        for (number, idx) in self.canonical_idxs().into_iter().enumerate() {
+            out.shape.layout[idx] = Some(number as u8 + 1);
+        }
+        out
+    }
+
+    /// Returns the cells of the cycle, in the order the food moves
+    /// through them.
+    pub(super) fn idxs(&self) -> Vec<usize> {
+        let mut idxs: Vec<_> = self.shape.layout.iter().enumerate()
+            .filter_map(|(idx, cell)| Some((*cell.as_ref()?, idx)))
+            .collect();
+        idxs.sort();
+        idxs.into_iter().map(|(_, idx)| idx).collect()
+    }
+
+    fn canonical_idxs(&self) -> Vec<usize> {
+        let mut idxs = self.idxs();
+        let first = idxs.iter().enumerate().min_by_key(|(_, idx)| **idx).map_or(0, |(pos, _)| pos);
+        idxs.rotate_left(first);
+        idxs
+    }
+}
+
+impl ToolKind for Cycle {
+    fn parse(string: &str) -> Result<Self, ParserError> {
+        Cycle::from_str(string)
+    }
+
+    fn name() -> &'static str { "cycle" }
+
+    fn transformations(&self) -> Vec<Tool> {
+        self.all_transformations().into_iter().map(Tool::new).collect()
+    }
+
+    fn apply(&self, board: &Board, _rules: &Rules) -> (Board, Vec<Tool>) {
+        (board.apply_cycle(self), Vec::new())
+    }
+
+    fn footprint(&self, _rules: &Rules) -> CellMask {
+        self.shape.mask()
+    }
+
+    fn food_effect(&self, _rules: &Rules) -> FoodEffect {
+        FoodEffect::CONSERVES
+    }
+
+    fn produces_tools(&self) -> bool { false }
+
+    fn map_symmetry(&self, symmetry: Symmetry) -> Option<Tool> {
+        let shape = self.shape.map_symmetry(symmetry);
+        Some(Tool::new(Cycle { shape }.normalized()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_too_short_validation() {
+        let err = Cycle::from_str("(1..)");
+        assert!(err.is_err(), "{err:?}");
+    }
+
+    #[test]
+    fn test_gap_validation() {
+        let err = Cycle::from_str("(13)");
+        assert!(err.is_err(), "{err:?}");
+    }
+
+    #[test]
+    fn test_repeat_validation() {
+        let err = Cycle::from_str("(121)");
+        assert!(err.is_err(), "{err:?}");
+    }
+
+    #[test]
+    fn test_symmetric_transformations() {
+        // every rotation of the square moves the food the same way,
+        // so only the translations remain.
+        let cycle = Cycle::from_str("(12)(43)").unwrap();
+        assert_eq!(cycle.all_transformations().len(), 4);
+    }
+
+    #[test]
+    fn test_reversed_transformations() {
+        // a 3-cycle in a line, and its 180˚ rotation, move the food
+        // in opposite directions, so both remain.
+        let cycle = Cycle::from_str("(123)").unwrap();
+        assert_eq!(cycle.all_transformations().len(), 3 * 4);
+    }
+}
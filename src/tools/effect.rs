@@ -0,0 +1,20 @@
+/// What a tool can do to how much food is on the board, for quick
+/// checks of whether a puzzle could possibly be solved.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FoodEffect {
+    /// The most of each ingredient (indexed by ingredient) the tool can
+    /// put onto the board from nowhere.
+    pub adds: [u8; 10],
+    /// The most cells the tool can paste copies of food into.
+    pub copies: u8,
+    /// Whether the tool can take food off the board, or cover it up.
+    pub removes: bool,
+}
+
+impl FoodEffect {
+    /// A tool which only moves food around.
+    pub const CONSERVES: Self = FoodEffect { adds: [0; 10], copies: 0, removes: false };
+
+    /// A tool which might do anything at all.
+    pub const UNKNOWN: Self = FoodEffect { adds: [9; 10], copies: 9, removes: true };
+}
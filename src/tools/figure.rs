@@ -1,8 +1,10 @@
 use std::fmt;
+use std::str::FromStr;
 use itertools::iproduct;
+use super::CellMask;
 
-pub(super) const SIZE: usize = 3;
-pub(super) const AREA: usize = SIZE * SIZE;
+pub const SIZE: usize = 3;
+pub const AREA: usize = SIZE * SIZE;
 
 #[repr(i8)]
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
@@ -13,7 +15,7 @@ pub enum Direction {
     Up = -(SIZE as i8),
 }
 
-pub(super) type ParserError = &'static str;
+pub type ParserError = &'static str;
 
 pub type Piece = Figure<u8>;
 pub type Shape = Figure<()>;
@@ -23,6 +25,12 @@ pub trait InbentoCell: Clone + PartialEq {
     fn to_char(&self) -> char;
     fn parse(c: char) -> Result<Self, ()> where Self: Sized;
     fn rotate(&self) -> Self;
+
+    /// Mirror the cell left-to-right, for cells with directionality.
+    fn flip_horizontal(&self) -> Self { self.clone() }
+
+    /// Mirror the cell top-to-bottom, for cells with directionality.
+    fn flip_vertical(&self) -> Self { self.clone() }
 }
 
 impl InbentoCell for () {
@@ -72,19 +80,42 @@ impl InbentoCell for Direction {
             Self::Left => Self::Up,
         }
     }
+
+    fn flip_horizontal(&self) -> Self {
+        match *self {
+            Self::Right => Self::Left,
+            Self::Left => Self::Right,
+            vertical => vertical,
+        }
+    }
+
+    fn flip_vertical(&self) -> Self {
+        match *self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            horizontal => horizontal,
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub struct Figure<T: InbentoCell> {
     pub(super) layout: [Option<T>; AREA],
     pub(super) rotatable: bool,
+    pub(super) mirrorable: bool,
     pub(super) bounding_width: usize,
     pub(super) bounding_height: usize,
 }
 
-impl<T: InbentoCell> Figure<T> {
-    pub fn from_str(string: &str) -> Result<Self, ParserError> {
+impl<T: InbentoCell> FromStr for Figure<T> {
+    type Err = ParserError;
+
+    fn from_str(string: &str) -> Result<Self, ParserError> {
         let string: String = string.split_whitespace().collect();
+        let (mirrorable, string) = match string.strip_prefix('~') {
+            Some(rest) => (true, rest),
+            None => (false, string.as_str()),
+        };
 
         // first pass to learn metadata
         let mut metadata_it = string.chars();
@@ -155,7 +186,10 @@ impl<T: InbentoCell> Figure<T> {
                     bounding_height += 1;
                     is_open = true;
                 }
-                '.' => writer.write(None)?,
+                '.' => {
+                    if !is_open { return Err("unexpected character outside of row") }
+                    writer.write(None)?;
+                }
                 ']' => {
                     if rotatable { return homogeneity_error }
                     if !is_open { return Err("unexpected end of row") }
@@ -179,7 +213,23 @@ impl<T: InbentoCell> Figure<T> {
         }
         if is_open { return Err("unterminated row") }
         let LayoutWriter { layout, .. } = writer;
-        Ok(Figure { layout, rotatable, bounding_width, bounding_height })
+        Ok(Figure { layout, rotatable, mirrorable, bounding_width, bounding_height })
+    }
+}
+
+impl<T: InbentoCell> Figure<T> {
+    /// The cells of this Figure, in row-major order over the 3×3 area.
+    pub fn cells(&self) -> &[Option<T>; AREA] {
+        &self.layout
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [Option<T>; AREA] {
+        &mut self.layout
+    }
+
+    /// The cells of the 3×3 area which this Figure covers.
+    pub fn mask(&self) -> CellMask {
+        (0..AREA).filter(|&idx| self.layout[idx].is_some()).collect()
     }
 
     /// Return a clone of this Figure rotated 90˚ clockwise. The Figure's
@@ -194,6 +244,7 @@ impl<T: InbentoCell> Figure<T> {
         let mut out = Self {
             layout: Default::default(),
             rotatable: self.rotatable,
+            mirrorable: self.mirrorable,
             bounding_width: self.bounding_height, // nb: swapped
             bounding_height: self.bounding_width, // nb: swapped
         };
@@ -210,6 +261,44 @@ impl<T: InbentoCell> Figure<T> {
         out
     }
 
+    /// Return a clone of this Figure mirrored left-to-right within its
+    /// bounding box. If the Figure's elements have directionality, they
+    /// will also be mirrored appropriately.
+    ///
+    /// eg:
+    ///  (12>)    (<21)
+    ///  (3..) => (..3)
+    pub fn flip_horizontal(&self) -> Self {
+        let mut out = self.clone();
+        for y in 0..self.bounding_height {
+            for x in 0..self.bounding_width {
+                let sidx = y * SIZE + x;
+                let didx = y * SIZE + self.bounding_width - x - 1;
+                out.layout[didx] = self.layout[sidx].as_ref().map(|ic| ic.flip_horizontal());
+            }
+        }
+        out
+    }
+
+    /// Return a clone of this Figure mirrored top-to-bottom within its
+    /// bounding box. If the Figure's elements have directionality, they
+    /// will also be mirrored appropriately.
+    ///
+    /// eg:
+    ///  (12^)    (3..)
+    ///  (3..) => (12v)
+    pub fn flip_vertical(&self) -> Self {
+        let mut out = self.clone();
+        for y in 0..self.bounding_height {
+            for x in 0..self.bounding_width {
+                let sidx = y * SIZE + x;
+                let didx = (self.bounding_height - y - 1) * SIZE + x;
+                out.layout[didx] = self.layout[sidx].as_ref().map(|ic| ic.flip_vertical());
+            }
+        }
+        out
+    }
+
     /// Return a clone of this Figure translated by some Δx and Δy.
     /// Expands the bounding width and height to the full 3×3 area,
     /// representing the Figure's absolute position within the space.
@@ -227,6 +316,7 @@ impl<T: InbentoCell> Figure<T> {
         let mut out = Self {
             layout: Default::default(),
             rotatable: self.rotatable,
+            mirrorable: self.mirrorable,
             bounding_width: SIZE,
             bounding_height: SIZE,
         };
@@ -298,11 +388,25 @@ impl<T: InbentoCell> Figure<T> {
         rotations
     }
 
+    /// Returns a Vec of the *unique* reflections of this Figure, including
+    /// the Figure itself: mirrored left-to-right, top-to-bottom, and both.
+    ///
+    /// Note that the exact order of the reflections should not be relied on.
+    /// Note also that this function will provide reflections regardless of
+    /// the `mirrorable` flag.
+    fn all_reflections(&self) -> Vec<Self> {
+        let horizontal = self.flip_horizontal();
+        let both = horizontal.flip_vertical();
+        let vertical = self.flip_vertical();
+        dedup_unordered(vec![self.clone(), horizontal, vertical, both])
+    }
+
     /// Returns a Vec of the *unique* transformations that may be applied
     /// to this Figure. That is, all the translations of all the unique
     /// rotations of the Figure.
     ///
     /// If the Figure is not `rotatable`, returns only the translations.
+    /// If the Figure is `mirrorable`, its reflections are included too.
     ///
     /// eg, for the Piece:
     ///   (123)
@@ -315,34 +419,61 @@ impl<T: InbentoCell> Figure<T> {
     ///
     /// Note that the exact order of the transformations should not be relied on.
     pub fn all_transformations(&self) -> Vec<Self> {
-        if !self.rotatable {
-            return self.all_translations().collect();
+        let orientations = match (self.rotatable, self.mirrorable) {
+            (false, false) => return self.all_translations().collect(),
+            (false, true) => self.all_reflections(),
+            (true, false) => self.all_rotations(),
+            (true, true) => {
+                let mut rotations = self.all_rotations();
+                rotations.extend(self.flip_horizontal().all_rotations());
+                dedup_unordered(rotations)
+            }
+        };
+        let placements = orientations.iter().flat_map(|aligned| aligned.all_translations()).collect();
+        // orientations of a figure with empty rows or columns at its edge
+        // can have placements in common.
+        dedup_unordered(placements)
+    }
+}
+
+/// Remove all duplicates from the Vec, not just consecutive ones,
+/// keeping the first occurrence of each.
+fn dedup_unordered<T: PartialEq>(vec: Vec<T>) -> Vec<T> {
+    let mut out = Vec::with_capacity(vec.len());
+    for elem in vec {
+        if !out.contains(&elem) {
+            out.push(elem);
         }
-        let rotations = self.all_rotations();
-        rotations.iter().flat_map(|aligned| aligned.all_translations()).collect()
     }
+    out
 }
 
+impl<T: InbentoCell> Figure<T> {
+    fn write_row(&self, f: &mut fmt::Formatter, y: usize) -> fmt::Result {
+        write!(f, "{}", if self.rotatable { '(' } else { '[' })?;
+        for x in 0..self.bounding_width {
+            let idx = y * SIZE + x;
+            write!(f, "{}", match &self.layout[idx] {
+                None => '.',
+                Some(c) => InbentoCell::to_char(c),
+            })?;
+        }
+        write!(f, "{}", if self.rotatable { ')' } else { ']' })
+    }
+}
+
+/// Writes the Figure a row to a line, as it looks.
 impl<T: InbentoCell> fmt::Debug for Figure<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let write_row = |f: &mut fmt::Formatter, y: usize| {
-            write!(f, "{}", if self.rotatable { '(' } else { '[' })?;
-            for x in 0..self.bounding_width {
-                let idx = y * SIZE + x;
-                write!(f, "{}", match &self.layout[idx] {
-                    None => '.',
-                    Some(c) => InbentoCell::to_char(c),
-                })?;
-            }
-            write!(f, "{}", if self.rotatable { ')' } else { ']' })
-        };
-
+        if self.mirrorable {
+            write!(f, "~")?;
+        }
         if self.bounding_height == 1 {
-            write_row(f, 0)?;
+            self.write_row(f, 0)?;
         } else {
             writeln!(f)?;
             for y in 0..self.bounding_height {
-                write_row(f, y)?;
+                self.write_row(f, y)?;
                 writeln!(f)?;
             }
         }
@@ -350,16 +481,39 @@ impl<T: InbentoCell> fmt::Debug for Figure<T> {
     }
 }
 
+/// Writes the Figure on a single line, as `from_str` reads it.
+impl<T: InbentoCell> fmt::Display for Figure<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.mirrorable {
+            write!(f, "~")?;
+        }
+        for y in 0..self.bounding_height {
+            self.write_row(f, y)?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use super::*;
-
-    // TODO: currently uhhh not orderless.
-    // until we implement this, this is more a way to document
-    // which eqs should be orderless.
-    macro_rules! assert_eq_orderless {
-        ($left:expr, $right:expr $(,)?) => { assert_eq!($left, $right) };
-        ($left:expr, $right:expr, $($arg:tt)+) => { assert_eq!($left, $right, $($arg)+) };
+    use crate::generator::Rng;
+    use crate::testing::{random_figure, CASES};
+
+    /// Run the body against random figures of every kind of cell.
+    macro_rules! for_each_figure {
+        (|$figure:ident| $body:expr) => {
+            for seed in 0..CASES {
+                let mut rng = Rng::new(seed);
+                let $figure: Piece = random_figure(&mut rng, &['1', '2', '3']);
+                $body;
+                let $figure: Shape = random_figure(&mut rng, &['#']);
+                $body;
+                let $figure: Push = random_figure(&mut rng, &['^', '>', 'v', '<']);
+                $body;
+            }
+        };
     }
 
     #[test]
@@ -368,6 +522,15 @@ mod tests {
         assert!(err.is_err(), "{err:?}");
     }
 
+    #[test]
+    fn test_empty_cell_outside_row() {
+        let err = Shape::from_str("(#.)(.#).");
+        assert!(err.is_err(), "{err:?}");
+        // found by fuzzing: this used to write past the end of the layout.
+        let err = Shape::from_str("(#.)(#.)(#.).(");
+        assert!(err.is_err(), "{err:?}");
+    }
+
     #[test]
     fn test_extra_opener() {
         let err = Shape::from_str("(#.#)(.#(.)");
@@ -439,4 +602,122 @@ mod tests {
             Shape::from_str("(..#)(...)(..#)").unwrap(),
         ]);
     }
+
+    #[test]
+    fn test_flip_horizontal_directions() {
+        let shape = Push::from_str("(^>)(<.)").unwrap();
+        assert_eq!(shape.flip_horizontal(), Push::from_str("(<^)(.>)").unwrap());
+    }
+
+    #[test]
+    fn test_flip_vertical_directions() {
+        let shape = Push::from_str("(^>)(<.)").unwrap();
+        assert_eq!(shape.flip_vertical(), Push::from_str("(<.)(v>)").unwrap());
+    }
+
+    #[test]
+    fn test_mirrorable_notation() {
+        let shape = Piece::from_str("~(12)(3.)").unwrap();
+        assert!(shape.mirrorable);
+        assert_eq!(Piece::from_str(&format!("{shape:?}")), Ok(shape));
+    }
+
+    #[test]
+    fn test_mirrorable_transformations() {
+        let shape = Shape::from_str("~[#.][##]").unwrap();
+        let transformations = shape.all_transformations();
+        let expected = vec![
+            Shape::from_str("~[#..][##.][...]").unwrap(),
+            Shape::from_str("~[.#.][.##][...]").unwrap(),
+            Shape::from_str("~[...][#..][##.]").unwrap(),
+            Shape::from_str("~[...][.#.][.##]").unwrap(),
+            Shape::from_str("~[.#.][##.][...]").unwrap(),
+            Shape::from_str("~[..#][.##][...]").unwrap(),
+            Shape::from_str("~[...][.#.][##.]").unwrap(),
+            Shape::from_str("~[...][..#][.##]").unwrap(),
+            Shape::from_str("~[##.][#..][...]").unwrap(),
+            Shape::from_str("~[.##][.#.][...]").unwrap(),
+            Shape::from_str("~[...][##.][#..]").unwrap(),
+            Shape::from_str("~[...][.##][.#.]").unwrap(),
+            Shape::from_str("~[##.][.#.][...]").unwrap(),
+            Shape::from_str("~[.##][..#][...]").unwrap(),
+            Shape::from_str("~[...][##.][.#.]").unwrap(),
+            Shape::from_str("~[...][.##][..#]").unwrap(),
+        ];
+        // too many to spell out in order.
+        assert_eq!(transformations.len(), expected.len());
+        for figure in expected {
+            assert!(transformations.contains(&figure), "{figure:?}");
+        }
+    }
+
+    #[test]
+    fn test_rotatable_mirrorable_transformations() {
+        let shape = Shape::from_str("~(#.)(##)(.#)").unwrap();
+        // four rotations of the S, and of its mirror image Z,
+        // only two of each of which are unique.
+        assert_eq!(shape.all_transformations().len(), 4 * 2);
+    }
+
+    #[test]
+    fn test_four_rotations() {
+        for_each_figure!(|figure| {
+            assert_eq!(figure.rotate().rotate().rotate().rotate(), figure);
+        });
+    }
+
+    #[test]
+    fn test_flips_undo_themselves() {
+        for_each_figure!(|figure| {
+            assert_eq!(figure.flip_horizontal().flip_horizontal(), figure);
+            assert_eq!(figure.flip_vertical().flip_vertical(), figure);
+            // flipping both ways is a half turn.
+            assert_eq!(figure.flip_horizontal().flip_vertical(), figure.rotate().rotate());
+        });
+    }
+
+    #[test]
+    fn test_transformations_unique() {
+        for_each_figure!(|figure| {
+            let transformations = figure.all_transformations();
+            let unique: HashSet<_> = transformations.iter().collect();
+            assert_eq!(unique.len(), transformations.len(), "{figure:?}: {transformations:?}");
+        });
+    }
+
+    #[test]
+    fn test_transformations_fit() {
+        for_each_figure!(|figure| {
+            let cells = figure.mask().len();
+            for placed in figure.all_transformations() {
+                assert_eq!((placed.bounding_width, placed.bounding_height), (SIZE, SIZE), "{figure:?}");
+                // nothing was pushed off the edge.
+                assert_eq!(placed.mask().len(), cells, "{figure:?}: {placed:?}");
+            }
+        });
+    }
+
+    #[test]
+    fn test_transformations_closed() {
+        for_each_figure!(|figure| {
+            let transformations = figure.all_transformations();
+            for placed in &transformations {
+                if figure.rotatable {
+                    assert!(transformations.contains(&placed.rotate()), "{figure:?}: {placed:?}");
+                }
+                if figure.mirrorable {
+                    assert!(transformations.contains(&placed.flip_horizontal()), "{figure:?}: {placed:?}");
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        assert_eq!(Push::from_str("~(v<)\n(.>)").unwrap().to_string(), "~(v<)(.>)");
+        for_each_figure!(|figure| {
+            assert_eq!(figure.to_string().parse(), Ok(figure.clone()));
+            assert_eq!(format!("{figure:?}").parse(), Ok(figure));
+        });
+    }
 }
@@ -0,0 +1,71 @@
+use std::fmt;
+use std::str::FromStr;
+use super::{Board, ParserError, Rules, Shape, CellMask, FoodEffect, Tool, ToolKind};
+
+/// Picks up the food under its `Shape`, producing a `Piece` which
+/// may be placed later.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Lift {
+    pub(super) shape: Shape,
+}
+
+impl fmt::Debug for Lift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.shape)
+    }
+}
+
+impl fmt::Display for Lift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.shape)
+    }
+}
+
+impl FromStr for Lift {
+    type Err = ParserError;
+
+    fn from_str(string: &str) -> Result<Self, ParserError> {
+        let shape = Shape::from_str(string)?;
+        Ok(Lift { shape })
+    }
+}
+
+impl Lift {
+    pub fn all_transformations(&self) -> Vec<Self> {
+        self.shape.all_transformations().into_iter()
+            .map(|shape| Lift { shape })
+            .collect()
+    }
+}
+
+impl ToolKind for Lift {
+    fn parse(string: &str) -> Result<Self, ParserError> {
+        Lift::from_str(string)
+    }
+
+    fn name() -> &'static str { "lift" }
+
+    fn transformations(&self) -> Vec<Tool> {
+        self.all_transformations().into_iter().map(Tool::new).collect()
+    }
+
+    fn apply(&self, board: &Board, _rules: &Rules) -> (Board, Vec<Tool>) {
+        let (board, piece) = board.apply_lift(&self.shape);
+        (board, vec![Tool::new(piece)])
+    }
+
+    fn footprint(&self, _rules: &Rules) -> CellMask {
+        self.shape.mask()
+    }
+
+    fn food_effect(&self, _rules: &Rules) -> FoodEffect {
+        // the piece picked up can only put back what was lifted.
+        FoodEffect { removes: true, ..FoodEffect::CONSERVES }
+    }
+
+    fn reach(&self, _rules: &Rules) -> CellMask {
+        // the piece picked up may be smaller than the lift,
+        // if some of the cells were empty, so could go anywhere.
+        CellMask::FULL
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+use super::{AREA, SIZE};
+
+/// A set of cells within the 3×3 area.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CellMask(u16);
+
+impl CellMask {
+    pub const EMPTY: Self = CellMask(0);
+    pub const FULL: Self = CellMask((1 << AREA) - 1);
+
+    pub fn contains(self, idx: usize) -> bool {
+        self.0 & (1 << idx) != 0
+    }
+
+    pub fn insert(&mut self, idx: usize) {
+        self.0 |= 1 << idx;
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        CellMask(self.0 | other.0)
+    }
+
+    pub fn is_disjoint(self, other: Self) -> bool {
+        self.0 & other.0 == 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn iter(self) -> impl Iterator<Item=usize> {
+        (0..AREA).filter(move |&idx| self.contains(idx))
+    }
+}
+
+impl FromIterator<usize> for CellMask {
+    fn from_iter<I: IntoIterator<Item=usize>>(iter: I) -> Self {
+        let mut mask = CellMask::EMPTY;
+        for idx in iter {
+            mask.insert(idx);
+        }
+        mask
+    }
+}
+
+impl fmt::Debug for CellMask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..SIZE {
+            write!(f, "[")?;
+            for x in 0..SIZE {
+                write!(f, "{}", if self.contains(y * SIZE + x) { '#' } else { '.' })?;
+            }
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,28 @@
 mod board;
 mod copy_paste;
+mod cycle;
+mod effect;
 mod figure;
+mod lift;
+mod mask;
+mod rotate;
+mod rules;
+mod slide;
 mod swap;
+mod symmetry;
+mod tool;
 
 pub use figure::*;
 pub use copy_paste::CopyPaste;
+pub use cycle::Cycle;
+pub use effect::FoodEffect;
 pub use board::Board;
+pub use lift::Lift;
+pub use mask::CellMask;
+pub use rotate::Rotate;
+pub use rules::{PushRule, Rules};
+pub use slide::Slide;
 pub use swap::Swap;
+pub use symmetry::Symmetry;
+pub(crate) use symmetry::{canonical_board, symmetries};
+pub use tool::{DynEq, DynHash, Registry, Tool, ToolKind};
@@ -0,0 +1,166 @@
+use std::fmt;
+use std::str::FromStr;
+use super::{Board, Figure, InbentoCell, ParserError, Rules, CellMask, FoodEffect, Symmetry, Tool, ToolKind, SIZE};
+use super::board::{min_x, min_y, max_x, max_y};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum Spin {
+    Clockwise,
+    Counterclockwise,
+}
+
+impl InbentoCell for Spin {
+    fn to_char(&self) -> char {
+        match self {
+            Spin::Clockwise => 'R',
+            Spin::Counterclockwise => 'L',
+        }
+    }
+
+    fn parse(c: char) -> Result<Self, ()> {
+        match c {
+            'R' => Ok(Spin::Clockwise),
+            'L' => Ok(Spin::Counterclockwise),
+            _ => Err(()),
+        }
+    }
+
+    fn rotate(&self) -> Self { *self }
+
+    fn flip_horizontal(&self) -> Self {
+        match self {
+            Spin::Clockwise => Spin::Counterclockwise,
+            Spin::Counterclockwise => Spin::Clockwise,
+        }
+    }
+
+    fn flip_vertical(&self) -> Self { self.flip_horizontal() }
+}
+
+/// Turns the food under its region a quarter turn, clockwise (`R`)
+/// or counterclockwise (`L`). The region must look the same after a
+/// quarter turn, so that the food stays within it.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Rotate {
+    pub(super) shape: Figure<Spin>,
+}
+
+impl fmt::Debug for Rotate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.shape)
+    }
+}
+
+impl fmt::Display for Rotate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.shape)
+    }
+}
+
+impl FromStr for Rotate {
+    type Err = ParserError;
+
+    fn from_str(string: &str) -> Result<Self, ParserError> {
+        let shape: Figure<Spin> = Figure::from_str(string)?;
+        let mut spins = shape.layout.iter().flatten();
+        let spin = spins.next().ok_or("expected at least one rotate cell")?;
+        if spins.any(|other| other != spin) {
+            return Err("rotate cells must all turn the same way");
+        }
+        let rotate = Rotate { shape };
+        let is_square = max_x(&rotate.shape.layout) - min_x(&rotate.shape.layout)
+            == max_y(&rotate.shape.layout) - min_y(&rotate.shape.layout);
+        if !is_square || rotate.turns().any(|(_, dest)| rotate.shape.layout[dest].is_none()) {
+            return Err("rotate region must look the same after a quarter turn");
+        }
+        Ok(rotate)
+    }
+}
+
+impl Rotate {
+    pub fn all_transformations(&self) -> Vec<Self> {
+        self.shape.all_transformations().into_iter()
+            .map(|shape| Rotate { shape })
+            .collect()
+    }
+
+    fn is_clockwise(&self) -> bool {
+        self.shape.layout.iter().flatten().all(|spin| *spin == Spin::Clockwise)
+    }
+
+    /// Returns the (source, destination) index of each cell under the
+    /// region, turning it a quarter turn within its bounding box.
+    /// Assumes the region is square, which `from_str` validates.
+    pub(super) fn turns(&self) -> impl Iterator<Item=(usize, usize)> + '_ {
+        let layout = &self.shape.layout;
+        let left = min_x(layout);
+        let top = min_y(layout);
+        let size = max_x(layout) - left;
+        let clockwise = self.is_clockwise();
+        layout.iter().enumerate()
+            .filter(|(_, cell)| cell.is_some())
+            .map(move |(src, _)| {
+                let sx = src % SIZE - left;
+                let sy = src / SIZE - top;
+                let (dx, dy) = if clockwise {
+                    (size - sy - 1, sx)
+                } else {
+                    (sy, size - sx - 1)
+                };
+                (src, (dy + top) * SIZE + dx + left)
+            })
+    }
+}
+
+impl ToolKind for Rotate {
+    fn parse(string: &str) -> Result<Self, ParserError> {
+        Rotate::from_str(string)
+    }
+
+    fn name() -> &'static str { "rotate" }
+
+    fn transformations(&self) -> Vec<Tool> {
+        self.all_transformations().into_iter().map(Tool::new).collect()
+    }
+
+    fn apply(&self, board: &Board, _rules: &Rules) -> (Board, Vec<Tool>) {
+        (board.apply_rotate(self), Vec::new())
+    }
+
+    fn footprint(&self, _rules: &Rules) -> CellMask {
+        self.shape.mask()
+    }
+
+    fn food_effect(&self, _rules: &Rules) -> FoodEffect {
+        FoodEffect::CONSERVES
+    }
+
+    fn produces_tools(&self) -> bool { false }
+
+    fn map_symmetry(&self, symmetry: Symmetry) -> Option<Tool> {
+        Some(Tool::new(Rotate { shape: self.shape.map_symmetry(symmetry) }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixed_spin_validation() {
+        let err = Rotate::from_str("(RL)(RR)");
+        assert!(err.is_err(), "{err:?}");
+    }
+
+    #[test]
+    fn test_asymmetric_validation() {
+        let err = Rotate::from_str("(RR)");
+        assert!(err.is_err(), "{err:?}");
+    }
+
+    #[test]
+    fn test_ring() {
+        let rotate = Rotate::from_str("(RRR)(R.R)(RRR)");
+        assert!(rotate.is_ok(), "{rotate:?}");
+    }
+}
@@ -0,0 +1,17 @@
+/// How a `Push` treats food which is in the way of the food it moves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PushRule {
+    /// Pushed food lands on top of whatever is in its way.
+    #[default]
+    Overwrite,
+    /// Pushed food shoves the line of food ahead of it along by one.
+    /// If the line would be shoved off the board, none of it moves.
+    Shove,
+}
+
+/// Variations on the game's mechanics which the solver may be asked
+/// to follow.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rules {
+    pub push: PushRule,
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+use std::str::FromStr;
+use super::board::lane;
+use super::{Board, CellMask, FoodEffect, ParserError, Push, Rules, Symmetry, Tool, ToolKind};
+
+/// Slides the food under each arrow in its direction until it meets
+/// the edge or other food. Written just like a `Push`.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Slide {
+    pub(super) shape: Push,
+}
+
+impl fmt::Debug for Slide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.shape)
+    }
+}
+
+impl fmt::Display for Slide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.shape)
+    }
+}
+
+impl FromStr for Slide {
+    type Err = ParserError;
+
+    fn from_str(string: &str) -> Result<Self, ParserError> {
+        let shape = Push::from_str(string)?;
+        Ok(Slide { shape })
+    }
+}
+
+impl Slide {
+    pub fn all_transformations(&self) -> Vec<Self> {
+        self.shape.all_transformations().into_iter()
+            .map(|shape| Slide { shape })
+            .collect()
+    }
+}
+
+impl ToolKind for Slide {
+    fn parse(string: &str) -> Result<Self, ParserError> {
+        Slide::from_str(string)
+    }
+
+    fn name() -> &'static str { "slide" }
+
+    fn transformations(&self) -> Vec<Tool> {
+        self.all_transformations().into_iter().map(Tool::new).collect()
+    }
+
+    fn apply(&self, board: &Board, _rules: &Rules) -> (Board, Vec<Tool>) {
+        (board.apply_slide(self), Vec::new())
+    }
+
+    fn footprint(&self, _rules: &Rules) -> CellMask {
+        self.shape.cells().iter().enumerate()
+            .filter_map(|(src, dir)| Some(lane(src, (*dir)?)))
+            .fold(CellMask::EMPTY, CellMask::union)
+    }
+
+    fn food_effect(&self, _rules: &Rules) -> FoodEffect {
+        FoodEffect::CONSERVES
+    }
+
+    fn produces_tools(&self) -> bool { false }
+
+    fn map_symmetry(&self, symmetry: Symmetry) -> Option<Tool> {
+        Some(Tool::new(Slide { shape: self.shape.map_symmetry(symmetry) }))
+    }
+}
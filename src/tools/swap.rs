@@ -1,10 +1,43 @@
+use std::collections::HashSet;
 use std::fmt;
-use crate::try_into_array;
-use super::{Shape, ParserError};
+use std::str::FromStr;
+use super::{Board, Figure, InbentoCell, ParserError, Rules, CellMask, FoodEffect, Symmetry, Tool, ToolKind};
 
+/// Swaps the food under each pair of cells. A single pair is marked
+/// with `#`s:
+///   (#.#)
+/// and several pairs by numbering both cells of each pair, from 1
+/// without gaps:
+///   (12)
+///   (12)
+/// swaps the food in the top row with the food in the bottom row.
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Swap {
-    shape: Shape,
+    shape: Figure<SwapCell>,
+}
+
+/// A cell of a `Swap`: the number of the pair it's in, or 0 for the
+/// `#`s of a single pair.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct SwapCell(u8);
+
+impl InbentoCell for SwapCell {
+    fn to_char(&self) -> char {
+        match self.0 {
+            0 => '#',
+            pair => (pair + b'0') as char,
+        }
+    }
+
+    fn parse(c: char) -> Result<Self, ()> {
+        match c {
+            '#' => Ok(SwapCell(0)),
+            '1'..='9' => Ok(SwapCell(c as u8 - b'0')),
+            _ => Err(()),
+        }
+    }
+
+    fn rotate(&self) -> Self { *self }
 }
 
 impl fmt::Debug for Swap {
@@ -13,34 +46,102 @@ impl fmt::Debug for Swap {
     }
 }
 
-impl Swap {
-    #[allow(dead_code)]
-    pub fn from_str(string: &str) -> Result<Self, ParserError> {
-        let shape = Shape::from_str(string)?;
-        let swapped_idxs = shape.layout.iter().enumerate()
-            .filter(|(_, cell)| cell.is_some())
-            .map(|(idx, _)| idx);
-        let _cells: [_; 2] = try_into_array(swapped_idxs)
-            .map_err(|_| "expected exactly two swap cells")?;
+impl fmt::Display for Swap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.shape)
+    }
+}
+
+impl FromStr for Swap {
+    type Err = ParserError;
+
+    fn from_str(string: &str) -> Result<Self, ParserError> {
+        let shape: Figure<SwapCell> = Figure::from_str(string)?;
+        let mut pairs: Vec<_> = shape.layout.iter().flatten().map(|cell| cell.0).collect();
+        pairs.sort();
+        if pairs.contains(&0) {
+            if pairs != [0, 0] {
+                return Err("expected exactly two swap cells");
+            }
+        } else if pairs.is_empty() || !pairs.iter().copied().eq((1..=pairs.len() as u8 / 2).flat_map(|pair| [pair, pair])) {
+            return Err("swap pairs must be numbered from 1 without gaps, with two cells each");
+        }
         Ok(Swap { shape })
     }
 }
 
 impl Swap {
+    /// Returns the *unique* transformations of this Swap. Transformations
+    /// which swap the same pairs of cells are equivalent, no matter how
+    /// the pairs are numbered.
     pub fn all_transformations(&self) -> Vec<Self> {
+        let mut seen = HashSet::new();
         self.shape.all_transformations().into_iter()
-            .map(|shape| Swap { shape })
+            .map(|shape| Swap { shape }.normalized())
+            .filter(|swap| seen.insert(swap.pairs()))
             .collect()
     }
 
-    pub(super) fn swap_idxs(&self) -> [usize; 2] {
-        let swapped_idxs = self.shape.layout.iter().enumerate()
-            .filter(|(_, cell)| cell.is_some())
-            .map(|(idx, _)| idx);
-        try_into_array(swapped_idxs).unwrap()
+    /// Renumber the pairs in the order of their first cells, so that
+    /// swaps of the same pairs of cells are equal.
+    fn normalized(&self) -> Self {
+        let mut out = self.clone();
+        if self.shape.layout.iter().flatten().any(|cell| cell.0 != 0) {
+            for (number, pair) in self.pairs().into_iter().enumerate() {
+                for idx in pair {
+                    out.shape.layout[idx] = Some(SwapCell(number as u8 + 1));
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns the pairs of cells whose food is swapped, ordered by
+    /// their first cells.
+    pub(super) fn pairs(&self) -> Vec<[usize; 2]> {
+        let mut pairs = Vec::new();
+        let mut unpaired: Vec<(SwapCell, usize)> = Vec::new();
+        for (idx, cell) in self.shape.layout.iter().enumerate() {
+            let Some(cell) = *cell else { continue };
+            match unpaired.iter().position(|(other, _)| *other == cell) {
+                Some(pos) => pairs.push([unpaired.swap_remove(pos).1, idx]),
+                None => unpaired.push((cell, idx)),
+            }
+        }
+        pairs.sort();
+        pairs
     }
 }
 
+impl ToolKind for Swap {
+    fn parse(string: &str) -> Result<Self, ParserError> {
+        Swap::from_str(string)
+    }
+
+    fn name() -> &'static str { "swap" }
+
+    fn transformations(&self) -> Vec<Tool> {
+        self.all_transformations().into_iter().map(Tool::new).collect()
+    }
+
+    fn apply(&self, board: &Board, _rules: &Rules) -> (Board, Vec<Tool>) {
+        (board.apply_swap(self), Vec::new())
+    }
+
+    fn footprint(&self, _rules: &Rules) -> CellMask {
+        self.shape.mask()
+    }
+
+    fn food_effect(&self, _rules: &Rules) -> FoodEffect {
+        FoodEffect::CONSERVES
+    }
+
+    fn produces_tools(&self) -> bool { false }
+
+    fn map_symmetry(&self, symmetry: Symmetry) -> Option<Tool> {
+        Some(Tool::new(Swap { shape: self.shape.map_symmetry(symmetry) }.normalized()))
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -56,5 +157,26 @@ mod tests {
     fn test_multi_swap_validation() {
         let err = Swap::from_str("(###)");
         assert!(err.is_err(), "{err:?}");
+        let err = Swap::from_str("(#1)(.1)");
+        assert!(err.is_err(), "{err:?}");
+        let err = Swap::from_str("(12)(.2)");
+        assert!(err.is_err(), "{err:?}");
+        let err = Swap::from_str("(13)(13)");
+        assert!(err.is_err(), "{err:?}");
+    }
+
+    #[test]
+    fn test_pairs() {
+        let swap = Swap::from_str("(2.1)(2.1)").unwrap();
+        assert_eq!(swap.pairs(), vec![[0, 3], [2, 5]]);
+        assert_eq!(swap.normalized(), Swap::from_str("(1.2)(1.2)").unwrap());
+    }
+
+    #[test]
+    fn test_symmetric_transformations() {
+        // turned halfway round, the rows swap the same cells, so only
+        // swapping rows or columns remains, in each corner.
+        let swap = Swap::from_str("(12)(12)").unwrap();
+        assert_eq!(swap.all_transformations().len(), 2 * 4);
     }
 }
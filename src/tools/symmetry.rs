@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+use super::{Board, Figure, InbentoCell, Tool, SIZE};
+
+/// One of the eight ways of turning or flipping the 3×3 board onto
+/// itself: mirrored left-to-right if `mirrored`, then turned a quarter
+/// turn clockwise `turns` times.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    turns: u8,
+    mirrored: bool,
+}
+
+impl Symmetry {
+    pub const IDENTITY: Self = Symmetry { turns: 0, mirrored: false };
+
+    /// Returns every symmetry of the board, starting with the identity.
+    pub fn all() -> impl Iterator<Item=Self> {
+        [false, true].into_iter()
+            .flat_map(|mirrored| (0..4).map(move |turns| Symmetry { turns, mirrored }))
+    }
+
+    /// The symmetry which does `self`, and then `other`.
+    pub fn then(self, other: Self) -> Self {
+        // a mirror turns the turns before it the other way.
+        let turns = if other.mirrored { 4 - self.turns } else { self.turns };
+        Symmetry {
+            turns: (turns + other.turns) % 4,
+            mirrored: self.mirrored != other.mirrored,
+        }
+    }
+
+    /// Number the symmetries from 0 to 7, for storing them compactly.
+    pub(crate) fn index(self) -> u8 {
+        self.mirrored as u8 * 4 + self.turns
+    }
+
+    pub(crate) fn from_index(index: u8) -> Self {
+        Symmetry { turns: index % 4, mirrored: index >= 4 }
+    }
+
+    /// The symmetry which undoes this one.
+    pub fn inverse(self) -> Self {
+        if self.mirrored {
+            // every mirror, however it's turned, undoes itself.
+            self
+        } else {
+            Symmetry { turns: (4 - self.turns) % 4, mirrored: false }
+        }
+    }
+}
+
+impl<T: InbentoCell> Figure<T> {
+    /// Return a clone of this Figure, as placed on the board, moved by
+    /// the symmetry. If the Figure's elements have directionality, they
+    /// will be turned and mirrored along with it.
+    pub fn map_symmetry(&self, symmetry: Symmetry) -> Self {
+        let mut out = self.clone();
+        out.bounding_width = SIZE;
+        out.bounding_height = SIZE;
+        if symmetry.mirrored {
+            out = out.flip_horizontal();
+        }
+        for _ in 0..symmetry.turns {
+            out = out.rotate();
+        }
+        out
+    }
+}
+
+/// Returns the symmetries which leave the goal unchanged, and which
+/// map every placement of each tool onto another placement of the same
+/// tool, starting with the identity. Under any of these, two boards
+/// which are images of each other are just as far from the goal.
+///
+/// Tools which can't map themselves under a symmetry (such as `Lift`,
+/// whose lifted pieces may not be symmetric), or which produce tools,
+/// leave only the identity, which is always included.
+pub(crate) fn symmetries(goal: &Board, tools: &[Tool]) -> Vec<Symmetry> {
+    // the tools produced along the way aren't known up front, so can't
+    // be checked.
+    if tools.iter().any(Tool::produces_tools) {
+        return vec![Symmetry::IDENTITY];
+    }
+    let placements: Vec<HashSet<Tool>> = tools.iter()
+        .map(|tool| tool.transformations().into_iter().collect())
+        .collect();
+    let mut found = vec![Symmetry::IDENTITY];
+    found.extend(Symmetry::all()
+        .skip(1)
+        .filter(|&symmetry| goal.map_symmetry(symmetry) == *goal)
+        .filter(|&symmetry| placements.iter().all(|placements| {
+            placements.iter().all(|placed| {
+                placed.map_symmetry(symmetry).is_some_and(|mapped| placements.contains(&mapped))
+            })
+        })));
+    found
+}
+
+/// Returns the least of the board's images under the symmetries, along
+/// with the symmetry which gives it, preferring the earliest symmetry.
+pub(crate) fn canonical_board(board: &Board, symmetries: &[Symmetry]) -> (Board, Symmetry) {
+    symmetries.iter()
+        .map(|&symmetry| (board.map_symmetry(symmetry), symmetry))
+        .min_by(|(a, _), (b, _)| a.cells().cmp(b.cells()))
+        .unwrap_or((board.clone(), Symmetry::IDENTITY))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use crate::tools::{Lift, Piece, Push, Swap};
+
+    #[test]
+    fn test_group() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        for a in Symmetry::all() {
+            assert_eq!(board.map_symmetry(a).map_symmetry(a.inverse()), board);
+            for b in Symmetry::all() {
+                assert_eq!(board.map_symmetry(a).map_symmetry(b), board.map_symmetry(a.then(b)));
+            }
+        }
+        assert!(Symmetry::all().enumerate().all(|(idx, symmetry)| symmetry.index() as usize == idx));
+        assert!(Symmetry::all().all(|symmetry| Symmetry::from_index(symmetry.index()) == symmetry));
+        let images: HashSet<_> = Symmetry::all().map(|symmetry| board.map_symmetry(symmetry)).collect();
+        assert_eq!(images.len(), 8);
+    }
+
+    #[test]
+    fn test_directions() {
+        let push = Push::from_str("[>..][...][...]").unwrap();
+        let turned = push.map_symmetry(Symmetry { turns: 1, mirrored: false });
+        assert_eq!(turned, Push::from_str("[..v][...][...]").unwrap());
+    }
+
+    #[test]
+    fn test_symmetries() {
+        let goal = Board::from_str("[121][212][121]").unwrap();
+        let swap = Tool::new(Swap::from_str("(##)").unwrap());
+        assert_eq!(symmetries(&goal, std::slice::from_ref(&swap)).len(), 8);
+        // a fixed piece can only be flipped top-to-bottom.
+        let piece = Tool::new(Piece::from_str("[12]").unwrap());
+        assert_eq!(symmetries(&goal, &[swap.clone(), piece]), vec![
+            Symmetry::IDENTITY,
+            Symmetry { turns: 2, mirrored: true },
+        ]);
+        let lift = Tool::new(Lift::from_str("(##)").unwrap());
+        assert_eq!(symmetries(&goal, &[swap, lift]), vec![Symmetry::IDENTITY]);
+    }
+}
@@ -0,0 +1,394 @@
+use std::any::{Any, TypeId};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::Arc;
+use super::board::{lane, step};
+use super::{Board, CellMask, CopyPaste, Cycle, Figure, FoodEffect, Lift, ParserError, Piece, Push, PushRule, Rotate, Rules, Slide, Swap, Symmetry};
+
+/// A kind of tool which may be placed on the board.
+///
+/// The solver only ever deals with tools through this trait, so new
+/// tools can be added (including from outside this crate) by
+/// implementing it and wrapping the value in a [`Tool`]. A kind's
+/// `Display` should write its figure on a single line, as `parse` reads it.
+pub trait ToolKind: fmt::Display + DynEq + DynHash + Send + Sync {
+    /// Parse the tool from its figure notation, eg `(v<)`.
+    fn parse(string: &str) -> Result<Self, ParserError> where Self: Sized;
+
+    /// The name of this kind of tool, eg `push`, used to label its tools
+    /// in puzzle files, and to read them back through a [`Registry`].
+    fn name() -> &'static str where Self: Sized;
+
+    /// Returns every placement of this tool on the board. Each placement
+    /// is itself a tool which may be `apply`'d.
+    fn transformations(&self) -> Vec<Tool>;
+
+    /// Apply this tool to the board as placed, following the given
+    /// `rules`, returning the resulting board along with any tools
+    /// produced by the application (eg, the `Piece` picked up by a `Lift`).
+    fn apply(&self, board: &Board, rules: &Rules) -> (Board, Vec<Tool>);
+
+    /// The cells which applying this tool as placed may read or change.
+    /// Tools which don't say are assumed to touch the whole board.
+    fn footprint(&self, _rules: &Rules) -> CellMask {
+        CellMask::FULL
+    }
+
+    /// The cells which this tool, placed anywhere, and any tools it
+    /// produces may read or change.
+    fn reach(&self, rules: &Rules) -> CellMask {
+        self.transformations().iter()
+            .map(|placed| placed.footprint(rules))
+            .fold(CellMask::EMPTY, CellMask::union)
+    }
+
+    /// What this tool can do to how much food is on the board.
+    /// Tools which don't say are assumed to be able to do anything.
+    fn food_effect(&self, _rules: &Rules) -> FoodEffect {
+        FoodEffect::UNKNOWN
+    }
+
+    /// Whether applying this tool may produce tools. Tools which don't
+    /// say are assumed to, which keeps the solver from making use of the
+    /// puzzle's symmetries, as the tools produced can't be checked to
+    /// map under them.
+    fn produces_tools(&self) -> bool {
+        true
+    }
+
+    /// This tool as placed, moved by a symmetry of the board, or `None`
+    /// if the tool can't say. Tools which don't say keep the solver from
+    /// making use of the puzzle's symmetries.
+    fn map_symmetry(&self, _symmetry: Symmetry) -> Option<Tool> {
+        None
+    }
+}
+
+/// Parses a kind of tool from its figure notation.
+type Parser = fn(&str) -> Result<Tool, ParserError>;
+
+/// The kinds of tool which may be read by name, eg from puzzle files.
+/// The default registry knows the built-in kinds, and kinds from outside
+/// the crate are added to it with `with`.
+#[derive(Clone)]
+pub struct Registry {
+    kinds: Vec<(&'static str, Parser)>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry { kinds: Vec::new() }
+            .with::<CopyPaste>()
+            .with::<Cycle>()
+            .with::<Lift>()
+            .with::<Piece>()
+            .with::<Push>()
+            .with::<Rotate>()
+            .with::<Slide>()
+            .with::<Swap>()
+    }
+}
+
+impl Registry {
+    /// Also read tools of kind `K`, by its `name`. It takes the place of
+    /// any kind already known by the same name.
+    pub fn with<K: ToolKind + 'static>(mut self) -> Self {
+        self.kinds.retain(|(name, _)| *name != K::name());
+        self.kinds.push((K::name(), |figure| K::parse(figure).map(Tool::new)));
+        self
+    }
+
+    /// The names of the kinds known, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item=&'static str> + '_ {
+        self.kinds.iter().map(|(name, _)| *name)
+    }
+
+    /// Parse a tool, by the `name` of its kind, from its figure notation.
+    pub fn parse(&self, name: &str, figure: &str) -> Result<Tool, ParserError> {
+        let (_, parse) = self.kinds.iter()
+            .find(|(kind, _)| *kind == name)
+            .ok_or("unknown kind of tool")?;
+        parse(figure)
+    }
+}
+
+/// Object-safe equality, so that `dyn ToolKind`s may be compared.
+/// Implemented for every `Eq` type.
+pub trait DynEq: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn dyn_eq(&self, other: &dyn Any) -> bool;
+}
+
+impl<T: Eq + Any> DynEq for T {
+    fn as_any(&self) -> &dyn Any { self }
+
+    fn dyn_eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<T>().is_some_and(|other| self == other)
+    }
+}
+
+/// Object-safe hashing, so that `dyn ToolKind`s may be hashed.
+/// Implemented for every `Hash` type.
+pub trait DynHash {
+    fn dyn_hash(&self, state: &mut dyn Hasher);
+}
+
+impl<T: Hash + Any> DynHash for T {
+    fn dyn_hash(&self, mut state: &mut dyn Hasher) {
+        // two different kinds of tool may well hash the same
+        // underlying figure, so distinguish them by type.
+        TypeId::of::<T>().hash(&mut state);
+        self.hash(&mut state);
+    }
+}
+
+/// A tool of any kind, along with the name of its kind. Cheap to clone.
+#[derive(Clone)]
+pub struct Tool {
+    name: &'static str,
+    kind: Arc<dyn ToolKind>,
+}
+
+impl Tool {
+    pub fn new<K: ToolKind + 'static>(kind: K) -> Self {
+        Tool { name: K::name(), kind: Arc::new(kind) }
+    }
+
+    /// Parse a tool of a built-in kind, by the `name` of its kind, from
+    /// its figure notation. See `Registry` for other kinds.
+    pub fn parse(name: &str, figure: &str) -> Result<Self, ParserError> {
+        Registry::default().parse(name, figure)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn kind(&self) -> &dyn ToolKind {
+        &*self.kind
+    }
+
+    pub fn downcast_ref<K: ToolKind + 'static>(&self) -> Option<&K> {
+        self.kind().as_any().downcast_ref()
+    }
+
+    pub fn transformations(&self) -> Vec<Tool> {
+        self.kind.transformations()
+    }
+
+    pub fn apply(&self, board: &Board, rules: &Rules) -> (Board, Vec<Tool>) {
+        self.kind.apply(board, rules)
+    }
+
+    pub fn footprint(&self, rules: &Rules) -> CellMask {
+        self.kind.footprint(rules)
+    }
+
+    pub fn reach(&self, rules: &Rules) -> CellMask {
+        self.kind.reach(rules)
+    }
+
+    pub fn food_effect(&self, rules: &Rules) -> FoodEffect {
+        self.kind.food_effect(rules)
+    }
+
+    pub fn produces_tools(&self) -> bool {
+        self.kind.produces_tools()
+    }
+
+    pub fn map_symmetry(&self, symmetry: Symmetry) -> Option<Tool> {
+        self.kind.map_symmetry(symmetry)
+    }
+}
+
+impl PartialEq for Tool {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind().dyn_eq(other.kind().as_any())
+    }
+}
+
+impl Eq for Tool {}
+
+impl Hash for Tool {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind().dyn_hash(state)
+    }
+}
+
+impl fmt::Debug for Tool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Writes the tool's name, then its figure on a single line, eg
+/// `push:(v<)`, as `from_str` reads it.
+impl fmt::Display for Tool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.name(), self.kind())
+    }
+}
+
+/// Parses a tool of a built-in kind, written as its name and figure,
+/// eg `push:(v<)`.
+impl FromStr for Tool {
+    type Err = ParserError;
+
+    fn from_str(string: &str) -> Result<Self, ParserError> {
+        let (name, figure) = string.split_once(':').ok_or("expected the tool's kind, then `:`, eg `push:(v<)`")?;
+        Tool::parse(name.trim(), figure)
+    }
+}
+
+// ===
+// figure tool kinds
+// ===
+impl ToolKind for Push {
+    fn parse(string: &str) -> Result<Self, ParserError> {
+        Push::from_str(string)
+    }
+
+    fn name() -> &'static str { "push" }
+
+    fn transformations(&self) -> Vec<Tool> {
+        self.all_transformations().into_iter().map(Tool::new).collect()
+    }
+
+    fn apply(&self, board: &Board, rules: &Rules) -> (Board, Vec<Tool>) {
+        let board = match rules.push {
+            PushRule::Overwrite => board.apply_push(self),
+            PushRule::Shove => board.apply_push_chain(self),
+        };
+        (board, Vec::new())
+    }
+
+    fn footprint(&self, rules: &Rules) -> CellMask {
+        // each pushed cell, and where its food lands. When shoving,
+        // that could be anywhere down the line.
+        let mut mask = CellMask::EMPTY;
+        for (src, dir) in self.cells().iter().enumerate() {
+            let Some(dir) = *dir else { continue };
+            mask = mask.union(match rules.push {
+                PushRule::Overwrite => step(src, dir).into_iter().chain([src]).collect(),
+                PushRule::Shove => lane(src, dir),
+            });
+        }
+        mask
+    }
+
+    fn food_effect(&self, rules: &Rules) -> FoodEffect {
+        // pushed food lands on top of whatever was there,
+        // unless it's shoved out of the way.
+        FoodEffect { removes: rules.push == PushRule::Overwrite, ..FoodEffect::CONSERVES }
+    }
+
+    fn produces_tools(&self) -> bool { false }
+
+    fn map_symmetry(&self, symmetry: Symmetry) -> Option<Tool> {
+        Some(Tool::new(Figure::map_symmetry(self, symmetry)))
+    }
+}
+
+impl ToolKind for Piece {
+    fn parse(string: &str) -> Result<Self, ParserError> {
+        Piece::from_str(string)
+    }
+
+    fn name() -> &'static str { "piece" }
+
+    fn transformations(&self) -> Vec<Tool> {
+        self.all_transformations().into_iter().map(Tool::new).collect()
+    }
+
+    fn apply(&self, board: &Board, _rules: &Rules) -> (Board, Vec<Tool>) {
+        (board.apply_piece(self), Vec::new())
+    }
+
+    fn footprint(&self, _rules: &Rules) -> CellMask {
+        self.mask()
+    }
+
+    fn food_effect(&self, _rules: &Rules) -> FoodEffect {
+        let mut adds = [0; 10];
+        for &ingredient in self.cells().iter().flatten() {
+            adds[ingredient as usize] += 1;
+        }
+        FoodEffect { adds, removes: true, ..FoodEffect::CONSERVES }
+    }
+
+    fn produces_tools(&self) -> bool { false }
+
+    fn map_symmetry(&self, symmetry: Symmetry) -> Option<Tool> {
+        Some(Tool::new(Figure::map_symmetry(self, symmetry)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Dispenser;
+
+    #[test]
+    fn test_kinds_are_distinct() {
+        let lift = Tool::new(Lift::from_str("(##)").unwrap());
+        let swap = Tool::new(Swap::from_str("(##)").unwrap());
+        assert_ne!(lift, swap);
+        assert_eq!(lift, Tool::new(Lift::from_str("(##)").unwrap()));
+    }
+
+    #[test]
+    fn test_parse_by_name() {
+        let tool = Tool::parse("swap", "(##)").unwrap();
+        assert_eq!(tool, Tool::new(Swap::from_str("(##)").unwrap()));
+        assert_eq!(tool.name(), "swap");
+        assert!(Tool::parse("bogus", "(##)").is_err());
+    }
+
+    #[test]
+    fn test_registry() {
+        assert!(Tool::parse("dispenser", "(##)").is_err());
+        let registry = Registry::default().with::<Dispenser>();
+        let tool = registry.parse("dispenser", "(##)").unwrap();
+        assert_eq!(tool, Tool::new(Dispenser::from_str("(##)").unwrap()));
+        assert_eq!(tool.name(), "dispenser");
+        let string = tool.to_string();
+        let (name, figure) = string.split_once(':').unwrap();
+        assert_eq!(registry.parse(name, figure), Ok(tool));
+        assert_eq!(registry.parse("swap", "(##)"), Tool::parse("swap", "(##)"));
+        assert_eq!(registry.names().count(), Registry::default().names().count() + 1);
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let tools = [
+            "push:(v<)", "piece:~[22][.2]", "swap:(#.#)", "swap:(12)(12)", "copy:(CC.)(.VV)",
+            "cycle:(12)(43)", "rotate:(RRR)(R.R)(RRR)", "lift:(##)", "slide:[>..][...][...]",
+        ];
+        for string in tools {
+            let tool: Tool = string.parse().unwrap();
+            assert_eq!(tool.to_string(), string);
+            assert_eq!(tool.to_string().parse(), Ok(tool));
+        }
+        assert_eq!("swap: (#.#)".parse(), Tool::parse("swap", "(#.#)"));
+        assert!("(#.#)".parse::<Tool>().is_err());
+    }
+
+    #[test]
+    fn test_push_footprint() {
+        let push = Tool::new(Push::from_str("[>..][...][...]").unwrap());
+        let overwrite = Rules { push: PushRule::Overwrite };
+        let shove = Rules { push: PushRule::Shove };
+        assert_eq!(push.footprint(&overwrite), [0, 1].into_iter().collect());
+        assert_eq!(push.footprint(&shove), [0, 1, 2].into_iter().collect());
+    }
+
+    #[test]
+    fn test_lift_produces_piece() {
+        let board = Board::from_str("[123][456][789]").unwrap();
+        let lift = Tool::new(Lift::from_str("[##.]").unwrap());
+        let (board, produced) = lift.apply(&board, &Rules::default());
+        assert_eq!(board, Board::from_str("[..3][456][789]").unwrap());
+        assert_eq!(produced, vec![Tool::new(Piece::from_str("[12]").unwrap())]);
+    }
+}